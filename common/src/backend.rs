@@ -0,0 +1,124 @@
+use crate::AppliedMigration;
+use futures_core::future::BoxFuture;
+use sqlx::{
+    database::HasArguments, ColumnIndex, Database, Decode, Encode, Executor, IntoArguments, Row,
+    Type,
+};
+
+/// A value bound to a bookkeeping statement.
+#[doc(hidden)]
+pub enum Argument {
+    Bool(bool),
+    Int(i64),
+    Text(String),
+    NullableText(Option<String>),
+}
+
+/// An sqlx database the migrator can run against.
+///
+/// This is implemented for every database whose driver can execute raw SQL
+/// and bind and decode booleans, integers and (nullable) strings. Which SQL is
+/// run for the bookkeeping table is up to the [`Dialect`](crate::Dialect).
+pub trait Backend: Database + Sized {
+    #[doc(hidden)]
+    fn execute<'c>(
+        conn: &'c mut Self::Connection,
+        sql: &'c str,
+        args: Vec<Argument>,
+    ) -> BoxFuture<'c, Result<(), sqlx::Error>>;
+
+    #[doc(hidden)]
+    fn execute_batch<'c>(
+        conn: &'c mut Self::Connection,
+        sql: &'c str,
+    ) -> BoxFuture<'c, Result<(), sqlx::Error>>;
+
+    #[doc(hidden)]
+    fn fetch_exists<'c>(
+        conn: &'c mut Self::Connection,
+        sql: &'c str,
+    ) -> BoxFuture<'c, Result<bool, sqlx::Error>>;
+
+    #[doc(hidden)]
+    fn fetch_applied<'c>(
+        conn: &'c mut Self::Connection,
+        sql: &'c str,
+    ) -> BoxFuture<'c, Result<Vec<AppliedMigration>, sqlx::Error>>;
+}
+
+impl<DB> Backend for DB
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    for<'a> &'a str: ColumnIndex<DB::Row>,
+    bool: Type<DB> + for<'q> Encode<'q, DB> + for<'r> Decode<'r, DB>,
+    i64: Type<DB> + for<'q> Encode<'q, DB> + for<'r> Decode<'r, DB>,
+    String: Type<DB> + for<'q> Encode<'q, DB> + for<'r> Decode<'r, DB>,
+    Option<String>: Type<DB> + for<'q> Encode<'q, DB>,
+{
+    fn execute<'c>(
+        conn: &'c mut Self::Connection,
+        sql: &'c str,
+        args: Vec<Argument>,
+    ) -> BoxFuture<'c, Result<(), sqlx::Error>> {
+        Box::pin(async move {
+            let mut query = sqlx::query(sql);
+
+            for arg in args {
+                query = match arg {
+                    Argument::Bool(value) => query.bind(value),
+                    Argument::Int(value) => query.bind(value),
+                    Argument::Text(value) => query.bind(value),
+                    Argument::NullableText(value) => query.bind(value),
+                };
+            }
+
+            query.execute(conn).await?;
+            Ok(())
+        })
+    }
+
+    fn execute_batch<'c>(
+        conn: &'c mut Self::Connection,
+        sql: &'c str,
+    ) -> BoxFuture<'c, Result<(), sqlx::Error>> {
+        // Executing a plain string skips preparation, which makes the drivers
+        // use their simple-query protocol and accept multiple statements.
+        Box::pin(async move {
+            conn.execute(sql).await?;
+            Ok(())
+        })
+    }
+
+    fn fetch_exists<'c>(
+        conn: &'c mut Self::Connection,
+        sql: &'c str,
+    ) -> BoxFuture<'c, Result<bool, sqlx::Error>> {
+        Box::pin(async move { Ok(sqlx::query(sql).fetch_optional(conn).await?.is_some()) })
+    }
+
+    fn fetch_applied<'c>(
+        conn: &'c mut Self::Connection,
+        sql: &'c str,
+    ) -> BoxFuture<'c, Result<Vec<AppliedMigration>, sqlx::Error>> {
+        Box::pin(async move {
+            sqlx::query(sql)
+                .fetch_all(conn)
+                .await?
+                .iter()
+                .map(|row| {
+                    Ok(AppliedMigration {
+                        applied_at: row.try_get("applied_at")?,
+                        checksum: row.try_get("checksum")?,
+                        execution_time: row.try_get("execution_time")?,
+                        name: row.try_get("name")?,
+                        namespace: row.try_get("namespace")?,
+                        success: row.try_get("success")?,
+                        version: row.try_get("version")?,
+                    })
+                })
+                .collect()
+        })
+    }
+}
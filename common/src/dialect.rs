@@ -0,0 +1,578 @@
+use crate::split::{split_statements, split_statements_for, Statement, Syntax};
+use sqlx::Database;
+use std::any::TypeId;
+
+/// The SQL flavour used to maintain the bookkeeping table.
+///
+/// Dialects for PostgreSQL, SQLite and MySQL/MariaDB are picked automatically
+/// from the database type. To run migrations against any other sqlx database,
+/// implement this trait and pass it to [`Migrator::dialect`].
+///
+/// Every statement receives the bookkeeping table as `table`, already quoted
+/// with [`quote_identifier`](Dialect::quote_identifier) and qualified with its
+/// schema, if one is configured.
+///
+/// [`Migrator::dialect`]: crate::Migrator::dialect
+pub trait Dialect: Send + Sync {
+    /// Quotes an identifier, escaping any quotes inside it.
+    fn quote_identifier(&self, ident: &str) -> String {
+        format!("\"{}\"", ident.replace('"', "\"\""))
+    }
+
+    /// Quotes a string literal, escaping any quotes inside it.
+    fn quote_literal(&self, value: &str) -> String {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+
+    /// Returns a row if the bookkeeping table exists. Unlike everywhere else,
+    /// `schema` and `table` are passed unquoted.
+    ///
+    /// Lets read-only operations like [`Migrator::plan`] skip creating the
+    /// table. Without it, they assume the table exists.
+    ///
+    /// [`Migrator::plan`]: crate::Migrator::plan
+    fn select_table(&self, _schema: Option<&str>, _table: &str) -> Option<String> {
+        None
+    }
+
+    /// Returns a row if the schema of the bookkeeping table exists. `schema`
+    /// is passed unquoted, as in [`select_table`](Dialect::select_table).
+    ///
+    /// Lets roles without the privilege to create schemas migrate into an
+    /// existing one. Without it, [`create_schema`](Dialect::create_schema)
+    /// runs on every migration.
+    fn select_schema(&self, _schema: &str) -> Option<String> {
+        None
+    }
+
+    /// Creates the schema of the bookkeeping table unless it already exists.
+    /// `schema` is quoted already.
+    fn create_schema(&self, _schema: &str) -> Option<String> {
+        None
+    }
+
+    /// Creates the bookkeeping table unless it already exists.
+    fn create_table(&self, table: &str) -> String;
+
+    /// Changes bringing a bookkeeping table created by an older version of
+    /// this crate up to date, run in order after
+    /// [`create_table`](Dialect::create_table). Besides the quoted `table`,
+    /// its `schema` and `name` are passed unquoted for catalog lookups, as in
+    /// [`select_table`](Dialect::select_table).
+    ///
+    /// Only run while migrating. Read-only operations like [`Migrator::plan`]
+    /// read an outdated table with the [`defaults`](Upgrade::defaults) of the
+    /// changes not made yet instead.
+    ///
+    /// [`Migrator::plan`]: crate::Migrator::plan
+    fn upgrade_table(&self, _table: &str, _schema: Option<&str>, _name: &str) -> Vec<Upgrade> {
+        vec![]
+    }
+
+    /// Selects the `namespace`, `version`, `name`, `checksum`, `success`,
+    /// `execution_time` and `applied_at` columns of all applied migrations of
+    /// every namespace, ordered by version. `applied_at` has to be text.
+    fn select_migrations(&self, table: &str) -> String;
+
+    /// Records a migration. Binds `version`, `name`, `checksum`,
+    /// `execution_time` in milliseconds, the nullable `source` directory and
+    /// `namespace`.
+    ///
+    /// Without transactional DDL the row has to be recorded as unsuccessful,
+    /// as it is written before the migration runs.
+    fn insert_migration(&self, table: &str) -> String;
+
+    /// Sets the success flag of a migration. Binds `success`, `execution_time`
+    /// in milliseconds, `version` and `namespace`.
+    ///
+    /// Only used when [`transactional_ddl`](Dialect::transactional_ddl) is
+    /// `false`.
+    fn update_migration(&self, _table: &str) -> Option<String> {
+        None
+    }
+
+    /// Removes a reverted migration. Binds `version` and `namespace`.
+    fn delete_migration(&self, table: &str) -> String;
+
+    /// Whether DDL statements can be rolled back as part of a transaction.
+    ///
+    /// Databases without transactional DDL (MySQL/MariaDB) commit implicitly on
+    /// every schema change. For those, the bookkeeping row is written up front
+    /// and only marked successful once every statement went through, so a
+    /// failed migration is recorded as partially applied instead of being
+    /// assumed to have rolled back.
+    fn transactional_ddl(&self) -> bool {
+        true
+    }
+
+    /// Splits a migration into the statements executed one by one. Defaults to
+    /// [`split_statements`], which follows PostgreSQL's syntax.
+    fn split_statements<'a>(&self, sql: &'a str) -> Vec<Statement<'a>> {
+        split_statements(sql)
+    }
+
+    /// Acquires the lock held for the whole migration run, waiting as long as
+    /// it takes. `key` is derived from the bookkeeping table.
+    fn lock(&self, _key: i64) -> Option<String> {
+        None
+    }
+
+    /// Acquires the migration lock without waiting. The statement has to
+    /// return a row if and only if the lock was acquired.
+    fn try_lock(&self, _key: i64) -> Option<String> {
+        None
+    }
+
+    /// Releases the lock taken by [`lock`](Dialect::lock) or
+    /// [`try_lock`](Dialect::try_lock).
+    fn unlock(&self, _key: i64) -> Option<String> {
+        None
+    }
+}
+
+/// A change to the bookkeeping table, see [`Dialect::upgrade_table`].
+pub struct Upgrade {
+    /// Returns a row if the change was made already.
+    pub check: String,
+    /// Run in order and in a transaction if `check` returns no row.
+    pub statements: Vec<String>,
+    /// Selected along with all columns of the table as long as `check`
+    /// returns no row, standing in for the columns the change adds, e.g.
+    /// `0 AS execution_time`.
+    pub defaults: Vec<String>,
+}
+
+/// Returns the dialect shipped for the given sqlx database, if any.
+pub fn for_database<DB: Database>() -> Option<&'static dyn Dialect> {
+    #[allow(unused_variables)]
+    let id = TypeId::of::<DB>();
+
+    #[cfg(feature = "mysql")]
+    if id == TypeId::of::<sqlx::MySql>() {
+        return Some(&MySqlDialect);
+    }
+
+    #[cfg(feature = "postgres")]
+    if id == TypeId::of::<sqlx::Postgres>() {
+        return Some(&PostgresDialect);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if id == TypeId::of::<sqlx::Sqlite>() {
+        return Some(&SqliteDialect);
+    }
+
+    None
+}
+
+/// Changes adding the `execution_time` and `source` columns, made the same
+/// way by every shipped dialect. `column` selects a row if the given column
+/// exists and `bigint` is the type 64-bit integers are cast to.
+fn add_columns(table: &str, bigint: &str, column: impl Fn(&str) -> String) -> Vec<Upgrade> {
+    vec![
+        Upgrade {
+            check: column("execution_time"),
+            statements: vec![format!(
+                "ALTER TABLE {} ADD COLUMN execution_time BIGINT NOT NULL DEFAULT 0",
+                table
+            )],
+            defaults: vec![format!("CAST(0 AS {}) AS execution_time", bigint)],
+        },
+        Upgrade {
+            check: column("source"),
+            statements: vec![format!("ALTER TABLE {} ADD COLUMN source TEXT", table)],
+            defaults: vec![String::from("NULL AS source")],
+        },
+    ]
+}
+
+/// Selects a row if `column` exists in `table`, looking in `current_schema`
+/// unless another `schema` is given. Names are passed unquoted.
+fn select_column(
+    dialect: &dyn Dialect,
+    current_schema: &str,
+    schema: Option<&str>,
+    table: &str,
+    column: &str,
+) -> String {
+    format!(
+        r#"
+            SELECT 1
+            FROM information_schema.columns
+            WHERE table_schema = {} AND table_name = {} AND column_name = {}
+        "#,
+        schema.map_or(String::from(current_schema), |s| dialect.quote_literal(s)),
+        dialect.quote_literal(table),
+        dialect.quote_literal(column)
+    )
+}
+
+pub struct PostgresDialect;
+
+impl Dialect for PostgresDialect {
+    fn select_table(&self, schema: Option<&str>, table: &str) -> Option<String> {
+        Some(format!(
+            r#"
+                SELECT 1
+                FROM information_schema.tables
+                WHERE table_schema = {} AND table_name = {}
+            "#,
+            schema.map_or(String::from("current_schema()"), |s| self.quote_literal(s)),
+            self.quote_literal(table)
+        ))
+    }
+
+    fn select_schema(&self, schema: &str) -> Option<String> {
+        Some(format!(
+            "SELECT 1 FROM information_schema.schemata WHERE schema_name = {}",
+            self.quote_literal(schema)
+        ))
+    }
+
+    fn create_schema(&self, schema: &str) -> Option<String> {
+        Some(format!("CREATE SCHEMA IF NOT EXISTS {}", schema))
+    }
+
+    fn create_table(&self, table: &str) -> String {
+        format!(
+            r#"
+                CREATE TABLE IF NOT EXISTS {} (
+                    namespace       VARCHAR(255) NOT NULL DEFAULT '',
+                    version         BIGINT NOT NULL,
+                    name            TEXT NOT NULL,
+                    checksum        VARCHAR(64),
+                    execution_time  BIGINT NOT NULL,
+                    source          TEXT,
+                    created_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+                    PRIMARY KEY (namespace, version)
+                );
+            "#,
+            table
+        )
+    }
+
+    fn upgrade_table(&self, table: &str, schema: Option<&str>, name: &str) -> Vec<Upgrade> {
+        let column = |column: &str| select_column(self, "current_schema()", schema, name, column);
+        let mut upgrades = add_columns(table, "BIGINT", &column);
+        upgrades.push(Upgrade {
+            check: column("namespace"),
+            statements: vec![
+                format!(
+                    "ALTER TABLE {} ADD COLUMN namespace VARCHAR(255) NOT NULL DEFAULT ''",
+                    table
+                ),
+                format!(
+                    r#"
+                        DO $$
+                        DECLARE
+                            pkey TEXT;
+                        BEGIN
+                            SELECT conname INTO pkey
+                            FROM pg_constraint
+                            WHERE conrelid = {0}::regclass AND contype = 'p';
+
+                            EXECUTE format('ALTER TABLE %s DROP CONSTRAINT %I', {0}, pkey);
+                        END
+                        $$
+                    "#,
+                    self.quote_literal(table)
+                ),
+                format!("ALTER TABLE {} ADD PRIMARY KEY (namespace, version)", table),
+            ],
+            defaults: vec![String::from("'' AS namespace")],
+        });
+
+        upgrades
+    }
+
+    fn select_migrations(&self, table: &str) -> String {
+        format!(
+            r#"
+                SELECT namespace, version, name, checksum, TRUE AS success, execution_time,
+                    CAST(created_at AS TEXT) AS applied_at
+                FROM {}
+                ORDER BY namespace, version
+            "#,
+            table
+        )
+    }
+
+    fn insert_migration(&self, table: &str) -> String {
+        format!(
+            r#"
+                INSERT INTO {} ( version, name, checksum, execution_time, source, namespace )
+                VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            table
+        )
+    }
+
+    fn delete_migration(&self, table: &str) -> String {
+        format!(
+            r#"
+                DELETE FROM {}
+                WHERE version = $1 AND namespace = $2
+            "#,
+            table
+        )
+    }
+
+    fn lock(&self, key: i64) -> Option<String> {
+        Some(format!("SELECT pg_advisory_lock({})", key))
+    }
+
+    fn try_lock(&self, key: i64) -> Option<String> {
+        Some(format!("SELECT 1 WHERE pg_try_advisory_lock({})", key))
+    }
+
+    fn unlock(&self, key: i64) -> Option<String> {
+        Some(format!("SELECT pg_advisory_unlock({})", key))
+    }
+}
+
+pub struct SqliteDialect;
+
+impl Dialect for SqliteDialect {
+    fn select_table(&self, schema: Option<&str>, table: &str) -> Option<String> {
+        Some(format!(
+            r#"
+                SELECT 1
+                FROM {}.sqlite_master
+                WHERE type = 'table' AND name = {}
+            "#,
+            self.quote_identifier(schema.unwrap_or("main")),
+            self.quote_literal(table)
+        ))
+    }
+
+    fn create_table(&self, table: &str) -> String {
+        format!(
+            r#"
+                CREATE TABLE IF NOT EXISTS {} (
+                    namespace       VARCHAR(255) NOT NULL DEFAULT '',
+                    version         BIGINT NOT NULL,
+                    name            TEXT NOT NULL,
+                    checksum        VARCHAR(64),
+                    execution_time  BIGINT NOT NULL,
+                    source          TEXT,
+                    created_at      TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (namespace, version)
+                );
+            "#,
+            table
+        )
+    }
+
+    fn upgrade_table(&self, table: &str, schema: Option<&str>, name: &str) -> Vec<Upgrade> {
+        // SQLite cannot change a primary key in place, so the table is
+        // rebuilt under its own name.
+        let old_name = format!("{}_old", name);
+        let old_table = match schema {
+            Some(schema) => format!(
+                "{}.{}",
+                self.quote_identifier(schema),
+                self.quote_identifier(&old_name)
+            ),
+            None => self.quote_identifier(&old_name),
+        };
+        let column = |column: &str| {
+            format!(
+                "SELECT 1 FROM pragma_table_info({}, {}) WHERE name = {}",
+                self.quote_literal(name),
+                self.quote_literal(schema.unwrap_or("main")),
+                self.quote_literal(column)
+            )
+        };
+
+        let mut upgrades = add_columns(table, "BIGINT", &column);
+        upgrades.push(Upgrade {
+            check: column("namespace"),
+            statements: vec![
+                format!(
+                    "ALTER TABLE {} RENAME TO {}",
+                    table,
+                    self.quote_identifier(&old_name)
+                ),
+                self.create_table(table),
+                format!(
+                    r#"
+                        INSERT INTO {}
+                            ( version, name, checksum, execution_time, source, created_at )
+                        SELECT version, name, checksum, execution_time, source, created_at
+                        FROM {}
+                    "#,
+                    table, old_table
+                ),
+                format!("DROP TABLE {}", old_table),
+            ],
+            defaults: vec![String::from("'' AS namespace")],
+        });
+
+        upgrades
+    }
+
+    fn select_migrations(&self, table: &str) -> String {
+        format!(
+            r#"
+                SELECT namespace, version, name, checksum, TRUE AS success, execution_time,
+                    CAST(created_at AS TEXT) AS applied_at
+                FROM {}
+                ORDER BY namespace, version
+            "#,
+            table
+        )
+    }
+
+    fn insert_migration(&self, table: &str) -> String {
+        format!(
+            r#"
+                INSERT INTO {} ( version, name, checksum, execution_time, source, namespace )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            table
+        )
+    }
+
+    fn delete_migration(&self, table: &str) -> String {
+        format!(
+            r#"
+                DELETE FROM {}
+                WHERE version = ?1 AND namespace = ?2
+            "#,
+            table
+        )
+    }
+
+    fn split_statements<'a>(&self, sql: &'a str) -> Vec<Statement<'a>> {
+        split_statements_for(sql, Syntax::Sqlite)
+    }
+}
+
+pub struct MySqlDialect;
+
+impl Dialect for MySqlDialect {
+    fn quote_identifier(&self, ident: &str) -> String {
+        format!("`{}`", ident.replace('`', "``"))
+    }
+
+    fn quote_literal(&self, value: &str) -> String {
+        format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+    }
+
+    fn select_table(&self, schema: Option<&str>, table: &str) -> Option<String> {
+        Some(format!(
+            r#"
+                SELECT 1
+                FROM information_schema.tables
+                WHERE table_schema = {} AND table_name = {}
+            "#,
+            schema.map_or(String::from("DATABASE()"), |s| self.quote_literal(s)),
+            self.quote_literal(table)
+        ))
+    }
+
+    fn create_table(&self, table: &str) -> String {
+        format!(
+            r#"
+                CREATE TABLE IF NOT EXISTS {} (
+                    namespace       VARCHAR(255) NOT NULL DEFAULT '',
+                    version         BIGINT NOT NULL,
+                    name            TEXT NOT NULL,
+                    checksum        VARCHAR(64),
+                    success         BOOLEAN NOT NULL,
+                    execution_time  BIGINT NOT NULL,
+                    source          TEXT,
+                    created_at      TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (namespace, version)
+                );
+            "#,
+            table
+        )
+    }
+
+    fn upgrade_table(&self, table: &str, schema: Option<&str>, name: &str) -> Vec<Upgrade> {
+        let column = |column: &str| select_column(self, "DATABASE()", schema, name, column);
+        let mut upgrades = add_columns(table, "SIGNED", &column);
+        upgrades.push(Upgrade {
+            check: column("namespace"),
+            statements: vec![format!(
+                r#"
+                    ALTER TABLE {}
+                        ADD COLUMN namespace VARCHAR(255) NOT NULL DEFAULT '' FIRST,
+                        DROP PRIMARY KEY,
+                        ADD PRIMARY KEY (namespace, version)
+                "#,
+                table
+            )],
+            defaults: vec![String::from("'' AS namespace")],
+        });
+
+        upgrades
+    }
+
+    fn select_migrations(&self, table: &str) -> String {
+        format!(
+            r#"
+                SELECT namespace, version, name, checksum, success, execution_time,
+                    CAST(created_at AS CHAR) AS applied_at
+                FROM {}
+                ORDER BY namespace, version
+            "#,
+            table
+        )
+    }
+
+    fn insert_migration(&self, table: &str) -> String {
+        format!(
+            r#"
+                INSERT INTO {} ( version, name, checksum, execution_time, source, namespace, success )
+                VALUES (?, ?, ?, ?, ?, ?, FALSE)
+            "#,
+            table
+        )
+    }
+
+    fn update_migration(&self, table: &str) -> Option<String> {
+        Some(format!(
+            r#"
+                UPDATE {}
+                SET success = ?, execution_time = ?
+                WHERE version = ? AND namespace = ?
+            "#,
+            table
+        ))
+    }
+
+    fn delete_migration(&self, table: &str) -> String {
+        format!(
+            r#"
+                DELETE FROM {}
+                WHERE version = ? AND namespace = ?
+            "#,
+            table
+        )
+    }
+
+    fn transactional_ddl(&self) -> bool {
+        false
+    }
+
+    fn split_statements<'a>(&self, sql: &'a str) -> Vec<Statement<'a>> {
+        split_statements_for(sql, Syntax::MySql)
+    }
+
+    fn lock(&self, key: i64) -> Option<String> {
+        Some(format!("SELECT GET_LOCK('sqlx_migrate_{}', -1)", key))
+    }
+
+    fn try_lock(&self, key: i64) -> Option<String> {
+        Some(format!(
+            "SELECT 1 FROM DUAL WHERE GET_LOCK('sqlx_migrate_{}', 0) = 1",
+            key
+        ))
+    }
+
+    fn unlock(&self, key: i64) -> Option<String> {
+        Some(format!("SELECT RELEASE_LOCK('sqlx_migrate_{}')", key))
+    }
+}
@@ -5,26 +5,114 @@ use quote::ToTokens;
 use quote::{quote, TokenStreamExt};
 use regex::Regex;
 use sha2::{Digest, Sha256};
-use sqlx::{
-    postgres::{PgPool, PgQueryResult, PgRow},
-    Executor, Row,
-};
-use std::convert::TryFrom;
+use sqlx::{Connection, Pool};
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
 use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+mod backend;
+pub mod dialect;
+mod naming;
+mod plan;
+mod policy;
+mod read;
+mod split;
+mod status;
+mod step;
+mod template;
+
+use backend::Argument;
+pub use backend::Backend;
+pub use dialect::Dialect;
+pub use futures_core::future::BoxFuture;
+pub use naming::NamingScheme;
+pub use plan::{ModifiedMigration, Plan, PlannedMigration};
+pub use policy::{MissingPolicy, OutOfOrder};
+pub use read::ReadOptions;
+pub use split::{split_statements, split_statements_for, Statement, Syntax};
+pub use status::{MigrationState, MigrationStatus};
+pub use step::{MigrationStep, Step};
+
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 lazy_static! {
-    static ref FILENAME_REGEX: Regex =
-        Regex::new(r"^(?P<version>[0-9]+)_(?P<name>[a-z_]+)\.sql$").unwrap();
+    static ref DIRECTIVE_REGEX: Regex = Regex::new(r"^--\s*sqlx-migrate:(?P<options>.*)$").unwrap();
 }
 
 #[derive(Error, Debug)]
 pub enum MigrationError {
-    #[error("Filename is invalid")]
-    FilenameError,
+    #[error("Invalid migration filename {filename}: {reason}")]
+    FilenameError { filename: String, reason: String },
+
+    #[error(
+        "Checksum of applied migration {version} ({name}) does not match: \
+         expected {expected}, found {actual}"
+    )]
+    ChecksumError {
+        version: i64,
+        name: String,
+        /// Checksum recorded when the migration was applied.
+        expected: String,
+        /// Checksum of the migration as it is now.
+        actual: String,
+    },
+
+    #[error("Migrations {first} and {second} have the same version {version}")]
+    DuplicateError {
+        version: i64,
+        first: String,
+        second: String,
+    },
+
+    #[error("Migration {0} cannot be reverted")]
+    IrreversibleError(i64),
+
+    #[error("Applied migrations are missing locally: {}", .0.join(", "))]
+    MissingError(Vec<String>),
+
+    #[error("Pending migrations are older than the latest applied one: {0:?}")]
+    OutOfOrderError(Vec<i64>),
+
+    #[error("Migrations were only partially applied and need to be repaired manually: {0:?}")]
+    PartialError(Vec<i64>),
+
+    #[error("Cannot read {filename}: {source}")]
+    ReadError {
+        filename: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Unknown sqlx-migrate directive `{directive}` in {filename}")]
+    DirectiveError { filename: String, directive: String },
+
+    #[error("Migration {0} is written in Rust for another database")]
+    StepError(i64),
+
+    #[error("Migration {version} uses the undefined variable `${{{name}}}`")]
+    VariableError { version: i64, name: String },
+
+    #[error("Timed out waiting for the migration lock")]
+    LockError,
 
-    #[error("Checksum of already applied migration does not match")]
-    ChecksumError,
+    #[error("No SQL dialect is available for this database")]
+    DialectError,
+
+    #[error("Statement {index} of migration {version} on line {line} failed: {source}\n{sql}")]
+    StatementError {
+        version: i64,
+        /// Position of the statement in the file, counting from 1.
+        index: usize,
+        line: usize,
+        sql: String,
+        #[source]
+        source: sqlx::Error,
+    },
 
     #[error(transparent)]
     SQLXError(#[from] sqlx::Error),
@@ -38,61 +126,385 @@ pub enum MigrationError {
 
 #[derive(Debug)]
 pub struct Migration {
+    /// Send the whole file as a single simple-query batch instead of executing
+    /// it statement by statement. Applies to the down migration as well.
+    pub batch: bool,
     pub checksum: String,
+    pub down_sql: Option<String>,
     pub name: String,
+    /// Run outside of a transaction, for statements like `CREATE INDEX
+    /// CONCURRENTLY` or `VACUUM`. Applies to the down migration as well.
+    ///
+    /// The migration is only recorded once all statements succeeded, so a
+    /// failure leaves it pending with whatever statements ran before in place.
+    pub no_transaction: bool,
+    /// Re-applied after all versioned migrations whenever its checksum
+    /// changes, for `CREATE OR REPLACE` definitions of views, functions and
+    /// triggers. Read from files named `R__<name>.sql`.
+    ///
+    /// Its `version` is derived from the name, only to key the bookkeeping
+    /// row that holds the last applied checksum. Repeatable migrations are
+    /// never reverted.
+    pub repeatable: bool,
+    /// The directory the migration was read from, as passed to `embed!` or
+    /// [`Migrator::from_dir`]. Recorded in the bookkeeping table.
+    pub source: Option<String>,
     pub sql: String,
+    /// Rust code run instead of `sql`, see [`Migrator::step`].
+    pub step: Option<Step>,
     pub version: i64,
 }
 
+impl fmt::Display for Migration {
+    /// `<source>/<version>_<name>`, or `<version>_<name>` without a source.
+    /// Repeatable migrations show as `R__<name>` instead.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(source) = &self.source {
+            write!(f, "{}/", source)?;
+        }
+
+        if self.repeatable {
+            write!(f, "R__{}", self.name)
+        } else {
+            write!(f, "{}_{}", self.version, self.name)
+        }
+    }
+}
+
+/// Options set in `-- sqlx-migrate: <option>, ...` comments at the top of a
+/// migration file.
+#[derive(Default)]
+struct Directives {
+    batch: bool,
+    no_transaction: bool,
+}
+
+impl Directives {
+    fn parse(filename: &str, sql: &str) -> Result<Self, MigrationError> {
+        let mut directives = Directives::default();
+
+        let header = sql
+            .lines()
+            .map(str::trim)
+            .take_while(|line| line.is_empty() || line.starts_with("--"));
+
+        for line in header {
+            if let Some(cap) = DIRECTIVE_REGEX.captures(line) {
+                for option in cap["options"].split(',').map(str::trim) {
+                    match option {
+                        "batch" => directives.batch = true,
+                        "no-transaction" => directives.no_transaction = true,
+                        "" => {}
+                        _ => {
+                            return Err(MigrationError::DirectiveError {
+                                filename: filename.to_owned(),
+                                directive: option.to_owned(),
+                            })
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(directives)
+    }
+}
+
+#[derive(PartialEq)]
+enum Direction {
+    Up,
+    Down,
+}
+
+struct FileName {
+    direction: Direction,
+    file_name: String,
+    name: String,
+    no_transaction: bool,
+    repeatable: bool,
+    version: i64,
+}
+
+impl FileName {
+    fn invalid(file_name: &str, reason: &str) -> MigrationError {
+        MigrationError::FilenameError {
+            filename: file_name.to_owned(),
+            reason: reason.to_owned(),
+        }
+    }
+
+    fn parse(entry: &DirEntry, naming: NamingScheme) -> Result<Self, MigrationError> {
+        let file_name_os = entry.file_name();
+        let file_name = file_name_os
+            .to_str()
+            .ok_or_else(|| FileName::invalid(&file_name_os.to_string_lossy(), "not valid UTF-8"))?;
+
+        if let Some(cap) = NamingScheme::repeatable_regex().captures(file_name) {
+            let name = cap["name"].to_owned();
+
+            return Ok(Self {
+                direction: Direction::Up,
+                file_name: file_name.to_owned(),
+                version: repeatable_version(&name),
+                name,
+                no_transaction: cap.name("notx").is_some(),
+                repeatable: true,
+            });
+        }
+
+        if file_name.starts_with("R__") {
+            return Err(FileName::invalid(
+                file_name,
+                "the name of a repeatable migration may only contain letters, digits, underscores and hyphens",
+            ));
+        }
+
+        let cap = naming
+            .regex()
+            .captures(file_name)
+            .ok_or_else(|| FileName::invalid(file_name, naming.mismatch_reason(file_name)))?;
+
+        let name = cap["name"].to_owned();
+
+        let version = cap["version"].parse().map_err(|_| {
+            FileName::invalid(file_name, "version does not fit into a 64-bit integer")
+        })?;
+
+        let direction = match (
+            cap.name("direction").map(|d| d.as_str()),
+            cap.name("prefix").map(|p| p.as_str()),
+        ) {
+            (Some("down"), _) | (_, Some("U")) => Direction::Down,
+            _ => Direction::Up,
+        };
+
+        let no_transaction = cap.name("notx").is_some();
+
+        if no_transaction && direction == Direction::Down {
+            return Err(FileName::invalid(
+                file_name,
+                "`.notx` is taken from the up migration and not allowed on down migrations",
+            ));
+        }
+
+        Ok(Self {
+            direction,
+            file_name: file_name.to_owned(),
+            name,
+            no_transaction,
+            repeatable: false,
+            version,
+        })
+    }
+}
+
+/// The version recording a repeatable migration, a negative number derived
+/// from its name so that it cannot clash with versioned migrations.
+fn repeatable_version(name: &str) -> i64 {
+    let digest = Sha256::digest(name.as_bytes());
+    -(i64::from_be_bytes(digest[..8].try_into().unwrap()) & i64::MAX) - 1
+}
+
+/// Orders versioned migrations by version, followed by repeatable ones by
+/// name, the order they run in.
+pub(crate) fn sort_key(repeatable: bool, version: i64, name: &str) -> (bool, i64, &str) {
+    if repeatable {
+        (true, 0, name)
+    } else {
+        (false, version, name)
+    }
+}
+
 impl TryFrom<DirEntry> for Migration {
     type Error = MigrationError;
 
     fn try_from(entry: DirEntry) -> Result<Self, Self::Error> {
-        let file_name_os = entry.file_name();
-        let file_name = file_name_os.to_str().ok_or(MigrationError::FilenameError)?;
+        let file_name = FileName::parse(&entry, NamingScheme::default())?;
 
-        let cap = FILENAME_REGEX
-            .captures(file_name)
-            .ok_or(MigrationError::FilenameError)?;
+        if file_name.direction == Direction::Down {
+            return Err(FileName::invalid(
+                &file_name.file_name,
+                "expected an up migration",
+            ));
+        }
 
-        let name = cap
-            .name("name")
-            .map(|name| name.as_str())
-            .ok_or(MigrationError::FilenameError)?
-            .to_owned();
+        let sql = read_sql(&entry.path(), &file_name.file_name)?;
 
-        let version = cap
-            .name("version")
-            .map(|version| version.as_str())
-            .ok_or(MigrationError::FilenameError)?
-            .parse()?;
+        Migration::from_file(file_name, sql)
+    }
+}
 
-        let sql = fs::read_to_string(&entry.path())?;
+impl Migration {
+    fn from_file(file_name: FileName, sql: String) -> Result<Self, MigrationError> {
         let checksum = format!("{:x}", Sha256::digest(sql.as_bytes()));
+        let directives = Directives::parse(&file_name.file_name, &sql)?;
 
         Ok(Self {
+            batch: directives.batch,
             checksum,
-            name,
+            down_sql: None,
+            name: file_name.name,
+            no_transaction: file_name.no_transaction || directives.no_transaction,
+            repeatable: file_name.repeatable,
+            source: None,
             sql,
-            version,
+            step: None,
+            version: file_name.version,
         })
     }
+
+    fn sort_key(&self) -> (bool, i64, &str) {
+        sort_key(self.repeatable, self.version, &self.name)
+    }
+}
+
+fn read_sql(path: &Path, filename: &str) -> Result<String, MigrationError> {
+    let read_error = |source| MigrationError::ReadError {
+        filename: filename.to_owned(),
+        source,
+    };
+
+    let bytes = fs::read(path).map_err(read_error)?;
+
+    String::from_utf8(bytes).map_err(|_| {
+        read_error(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not valid UTF-8",
+        ))
+    })
+}
+
+/// Reads all migrations from the given directory, ordered by version and
+/// followed by the repeatable migrations, ordered by name.
+///
+/// Subdirectories, dotfiles and files without the `.sql` extension are
+/// skipped, see [`ReadOptions`]. Files named `<version>_<name>.down.sql` are
+/// attached as `down_sql` to the migration with the same version and name.
+pub fn read_migrations<P: AsRef<Path>>(path: P) -> Result<Vec<Migration>, MigrationError> {
+    collect_migrations(path, &ReadOptions::default()).map_err(|mut errors| errors.remove(0))
+}
+
+/// Like [`read_migrations`], but keeps going after a file fails to load and
+/// returns every problem found, in file name order.
+pub fn collect_migrations<P: AsRef<Path>>(
+    path: P,
+    options: &ReadOptions,
+) -> Result<Vec<Migration>, Vec<MigrationError>> {
+    let path = path.as_ref();
+    let dir_error = |source| MigrationError::ReadError {
+        filename: path.display().to_string(),
+        source,
+    };
+
+    let mut entries = fs::read_dir(path)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|source| vec![dir_error(source)])?;
+    entries.sort_by_key(DirEntry::file_name);
+
+    let mut errors = vec![];
+    let mut migrations: Vec<Migration> = vec![];
+    let mut downs: HashMap<(i64, String), (FileName, String)> = HashMap::new();
+    let mut versions: HashMap<i64, String> = HashMap::new();
+
+    for entry in entries {
+        match options.includes(&entry) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        }
+
+        let loaded = FileName::parse(&entry, options.naming).and_then(|file_name| {
+            let sql = read_sql(&entry.path(), &file_name.file_name)?;
+
+            if file_name.direction == Direction::Down {
+                downs.insert(
+                    (file_name.version, file_name.name.clone()),
+                    (file_name, sql),
+                );
+            } else {
+                if let Some(first) = versions.get(&file_name.version) {
+                    return Err(MigrationError::DuplicateError {
+                        version: file_name.version,
+                        first: first.clone(),
+                        second: file_name.file_name,
+                    });
+                }
+
+                versions.insert(file_name.version, file_name.file_name.clone());
+                migrations.push(Migration::from_file(file_name, sql)?);
+            }
+
+            Ok(())
+        });
+
+        if let Err(err) = loaded {
+            errors.push(err);
+        }
+    }
+
+    for migration in &mut migrations {
+        migration.down_sql = downs
+            .remove(&(migration.version, migration.name.clone()))
+            .map(|(_, sql)| sql);
+    }
+
+    let mut orphans: Vec<FileName> = downs.into_values().map(|(f, _)| f).collect();
+    orphans.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    errors.extend(orphans.iter().map(|f| {
+        FileName::invalid(
+            &f.file_name,
+            "no up migration with the same version and name",
+        )
+    }));
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    migrations.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+
+    Ok(migrations)
 }
 
 impl ToTokens for Migration {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let Migration {
+            batch,
             checksum,
+            down_sql,
             name,
+            no_transaction,
+            repeatable,
+            source,
             sql,
             version,
+            ..
         } = &self;
 
+        let down_sql = match down_sql {
+            Some(down_sql) => quote! { Some(String::from(#down_sql)) },
+            None => quote! { None },
+        };
+
+        let source = match source {
+            Some(source) => quote! { Some(String::from(#source)) },
+            None => quote! { None },
+        };
+
         let ts = quote! {
             sqlx_migrate::Migration {
+                batch: #batch,
                 checksum: String::from(#checksum),
+                down_sql: #down_sql,
                 name: String::from(#name),
+                no_transaction: #no_transaction,
+                repeatable: #repeatable,
+                source: #source,
                 sql: String::from(#sql),
+                step: None,
                 version: #version,
             }
         };
@@ -101,101 +513,1119 @@ impl ToTokens for Migration {
     }
 }
 
-struct AppliedMigration {
+#[doc(hidden)]
+pub struct AppliedMigration {
+    applied_at: String,
     checksum: String,
+    execution_time: i64,
+    name: String,
+    namespace: String,
+    success: bool,
     version: i64,
 }
 
+impl AppliedMigration {
+    fn execution_time(&self) -> Duration {
+        Duration::from_millis(self.execution_time.max(0) as u64)
+    }
+
+    /// Whether the row records a repeatable migration, whose version is
+    /// derived from its name.
+    fn is_repeatable(&self) -> bool {
+        self.version == repeatable_version(&self.name)
+    }
+
+    /// `<version>_<name>`, or `R__<name>` for a repeatable migration.
+    fn label(&self) -> String {
+        if self.is_repeatable() {
+            format!("R__{}", self.name)
+        } else {
+            format!("{}_{}", self.version, self.name)
+        }
+    }
+
+    fn status(&self, state: MigrationState) -> MigrationStatus {
+        MigrationStatus {
+            applied_at: Some(self.applied_at.clone()),
+            checksum: self.checksum.clone(),
+            execution_time: Some(self.execution_time()),
+            name: self.name.clone(),
+            namespace: self.namespace.clone(),
+            repeatable: self.is_repeatable(),
+            state,
+            version: self.version,
+        }
+    }
+}
+
 pub struct Migrator {
     pub migrations: Vec<Migration>,
+    batch: bool,
+    dialect: Option<Box<dyn Dialect>>,
+    lock_timeout: Option<Duration>,
+    missing_policy: MissingPolicy,
+    namespace: String,
+    out_of_order: OutOfOrder,
+    schema: Option<String>,
+    table: String,
+    vars: HashMap<String, String>,
+}
+
+enum Target {
+    Latest,
+    Version(i64),
+    Rollback(usize),
 }
 
 impl Migrator {
+    /// # Panics
+    ///
+    /// If two migrations have the same version. Use
+    /// [`try_new`](Migrator::try_new) to handle that as an error instead.
     pub fn new(migrations: Vec<Migration>) -> Self {
-        Migrator { migrations }
+        Migrator::try_new(migrations).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`new`](Migrator::new), but fails with
+    /// [`MigrationError::DuplicateError`] if two migrations have the same
+    /// version.
+    pub fn try_new(mut migrations: Vec<Migration>) -> Result<Self, MigrationError> {
+        let mut versions: HashMap<i64, &Migration> = HashMap::new();
+
+        for migration in &migrations {
+            if let Some(first) = versions.insert(migration.version, migration) {
+                return Err(MigrationError::DuplicateError {
+                    version: migration.version,
+                    first: first.to_string(),
+                    second: migration.to_string(),
+                });
+            }
+        }
+
+        migrations.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+
+        Ok(Migrator {
+            migrations,
+            batch: false,
+            dialect: None,
+            lock_timeout: None,
+            missing_policy: MissingPolicy::default(),
+            namespace: String::new(),
+            out_of_order: OutOfOrder::default(),
+            schema: None,
+            table: String::from("migrations"),
+            vars: HashMap::new(),
+        })
+    }
+
+    /// Reads the migrations from a directory at run time, the same way
+    /// `embed!` does at compile time.
+    pub fn from_dir<P: AsRef<Path>>(path: P) -> Result<Self, MigrationError> {
+        Migrator::from_dir_with(path, &ReadOptions::default())
+    }
+
+    /// Like [`from_dir`](Migrator::from_dir), with the naming scheme and
+    /// strictness `embed!` takes as `naming` and `strict`.
+    pub fn from_dir_with<P: AsRef<Path>>(
+        path: P,
+        options: &ReadOptions,
+    ) -> Result<Self, MigrationError> {
+        let source = path.as_ref().display().to_string();
+        let mut migrations =
+            collect_migrations(path, options).map_err(|mut errors| errors.remove(0))?;
+
+        for migration in &mut migrations {
+            migration.source = Some(source.clone());
+        }
+
+        Migrator::try_new(migrations)
+    }
+
+    /// Like [`from_dir`](Migrator::from_dir), but reads the files on a
+    /// blocking thread instead of the async runtime.
+    pub async fn from_dir_async<P: AsRef<Path>>(path: P) -> Result<Self, MigrationError> {
+        Migrator::from_dir_with_async(path, &ReadOptions::default()).await
+    }
+
+    /// Like [`from_dir_with`](Migrator::from_dir_with), but reads the files
+    /// on a blocking thread instead of the async runtime.
+    pub async fn from_dir_with_async<P: AsRef<Path>>(
+        path: P,
+        options: &ReadOptions,
+    ) -> Result<Self, MigrationError> {
+        let path = path.as_ref().to_owned();
+        let options = options.clone();
+
+        sqlx_rt::blocking!(Migrator::from_dir_with(path, &options))
+    }
+
+    /// Adds a migration written in Rust, run in order of `version` among the
+    /// others. As code cannot be hashed, it is recorded with the given
+    /// `checksum`, which should change whenever the step does.
+    ///
+    /// # Panics
+    ///
+    /// If a migration with the same version exists already.
+    pub fn step<C: 'static, S: MigrationStep<C>>(
+        mut self,
+        version: i64,
+        name: &str,
+        checksum: &str,
+        step: S,
+    ) -> Self {
+        let migration = Migration {
+            batch: false,
+            checksum: checksum.to_owned(),
+            down_sql: None,
+            name: name.to_owned(),
+            no_transaction: false,
+            repeatable: false,
+            source: None,
+            sql: String::new(),
+            step: Some(Step::new(step)),
+            version,
+        };
+
+        if let Some(first) = self.migrations.iter().find(|m| m.version == version) {
+            panic!(
+                "{}",
+                MigrationError::DuplicateError {
+                    version,
+                    first: first.to_string(),
+                    second: migration.to_string(),
+                }
+            );
+        }
+
+        let index = self
+            .migrations
+            .partition_point(|m| m.sort_key() < migration.sort_key());
+
+        self.migrations.insert(index, migration);
+        self
+    }
+
+    /// Like [`step`](Migrator::step), for an async closure taking the
+    /// connection, e.g. `|conn: &mut PgConnection| Box::pin(async move { ... })`.
+    pub fn step_fn<C, F>(self, version: i64, name: &str, checksum: &str, step: F) -> Self
+    where
+        C: 'static,
+        F: for<'c> Fn(&'c mut C) -> BoxFuture<'c, Result<(), MigrationError>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.step(version, name, checksum, step)
+    }
+
+    /// Sends every migration as a single simple-query batch, as if it had the
+    /// `-- sqlx-migrate: batch` directive.
+    pub fn batch(mut self, batch: bool) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    /// Uses the given dialect instead of the one shipped for the database.
+    pub fn dialect<D: Dialect + 'static>(mut self, dialect: D) -> Self {
+        self.dialect = Some(Box::new(dialect));
+        self
+    }
+
+    /// Name of the bookkeeping table, `migrations` by default.
+    pub fn table(mut self, table: &str) -> Self {
+        self.table = table.to_owned();
+        self
+    }
+
+    /// Schema of the bookkeeping table. Uses the connection's default schema
+    /// unless set.
+    pub fn schema(mut self, schema: &str) -> Self {
+        self.schema = Some(schema.to_owned());
+        self
+    }
+
+    /// Keeps the history of this migrator apart from other migrators sharing
+    /// the bookkeeping table, e.g. those of libraries with their own
+    /// migrations. Each namespace has its own versions and only sees its own
+    /// applied migrations. Empty by default.
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.to_owned();
+        self
+    }
+
+    /// How to handle applied migrations that are not known locally. Fails by
+    /// default.
+    pub fn missing_policy(mut self, policy: MissingPolicy) -> Self {
+        self.missing_policy = policy;
+        self
+    }
+
+    /// How to handle pending migrations older than the latest applied one.
+    /// Applies them by default.
+    pub fn out_of_order(mut self, policy: OutOfOrder) -> Self {
+        self.out_of_order = policy;
+        self
+    }
+
+    /// Gives up with [`MigrationError::LockError`] if another process holds the
+    /// migration lock for longer than `timeout`. Waits indefinitely by default.
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = Some(timeout);
+        self
+    }
+
+    /// Replaces every `${name}` in the up and down migrations with the value
+    /// of `name` in `vars` before running them. Fails with
+    /// [`MigrationError::VariableError`] if a variable is not defined, which
+    /// includes running a migration with variables without setting any.
+    ///
+    /// Checksums are computed over the migrations as written, so they do not
+    /// depend on the values.
+    pub fn vars(mut self, vars: HashMap<String, String>) -> Self {
+        self.vars = vars;
+        self
+    }
+
+    pub async fn migrate<DB: Backend>(&self, db: &Pool<DB>) -> Result<(), MigrationError> {
+        self.run(db, Target::Latest, true).await?;
+        Ok(())
+    }
+
+    /// Sets [`vars`](Migrator::vars) and runs [`migrate`](Migrator::migrate).
+    pub async fn migrate_with_vars<DB: Backend>(
+        self,
+        db: &Pool<DB>,
+        vars: &HashMap<String, String>,
+    ) -> Result<(), MigrationError> {
+        self.vars(vars.clone()).migrate(db).await
+    }
+
+    /// Like [`migrate`](Migrator::migrate), but returns `Ok(false)` right away
+    /// instead of waiting if another process holds the migration lock.
+    pub async fn try_migrate<DB: Backend>(&self, db: &Pool<DB>) -> Result<bool, MigrationError> {
+        self.run(db, Target::Latest, false).await
+    }
+
+    /// Reverts the last `steps` applied migrations, newest first.
+    pub async fn rollback<DB: Backend>(
+        &self,
+        db: &Pool<DB>,
+        steps: usize,
+    ) -> Result<(), MigrationError> {
+        self.run(db, Target::Rollback(steps), true).await?;
+        Ok(())
+    }
+
+    /// Applies or reverts migrations until `target` is the latest applied version.
+    pub async fn migrate_to<DB: Backend>(
+        &self,
+        db: &Pool<DB>,
+        target: i64,
+    ) -> Result<(), MigrationError> {
+        self.run(db, Target::Version(target), true).await?;
+        Ok(())
     }
 
-    pub async fn migrate(&self, db: &PgPool) -> Result<(), MigrationError> {
-        self.ensure_table(db).await?;
+    /// Computes what [`migrate`](Migrator::migrate) would do, without
+    /// executing anything but reading the bookkeeping table.
+    pub async fn plan<DB: Backend>(&self, db: &Pool<DB>) -> Result<Plan, MigrationError> {
+        let dialect = self.dialect_for::<DB>()?;
+        let mut conn = db.acquire().await?;
 
-        let current = self.get_applied_migrations(db).await?;
+        let current = if self.table_exists::<DB>(&mut conn, dialect).await? {
+            let select = self.select_outdated::<DB>(&mut conn, dialect).await?;
+            self.get_applied_migrations::<DB>(&mut conn, &select)
+                .await?
+        } else {
+            vec![]
+        };
+
+        let mut plan = Plan {
+            missing: self
+                .missing_migrations(&current)
+                .map(|a| PlannedMigration {
+                    name: a.name.clone(),
+                    repeatable: a.is_repeatable(),
+                    version: a.version,
+                })
+                .collect(),
+            missing_policy: self.missing_policy,
+            out_of_order: self
+                .out_of_order_versions(&current, None)
+                .map(|(_, offending)| offending)
+                .unwrap_or_default(),
+            out_of_order_policy: self.out_of_order,
+            ..Plan::default()
+        };
 
         for migration in &self.migrations {
+            let planned = PlannedMigration {
+                name: migration.name.clone(),
+                repeatable: migration.repeatable,
+                version: migration.version,
+            };
+
             match current.iter().find(|a| a.version == migration.version) {
-                None => self.apply_migration(db, migration).await?,
-                Some(a) => {
-                    if a.checksum != migration.checksum {
-                        return Err(MigrationError::ChecksumError);
+                None => plan.pending.push(planned),
+                Some(a) if a.checksum != migration.checksum && migration.repeatable => {
+                    plan.pending.push(planned)
+                }
+                Some(a) if a.checksum != migration.checksum => {
+                    plan.modified.push(ModifiedMigration {
+                        applied_checksum: a.checksum.clone(),
+                        checksum: migration.checksum.clone(),
+                        name: planned.name,
+                        version: planned.version,
+                    })
+                }
+                Some(_) => plan.applied.push(planned),
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Lists every migration that is either known locally or recorded in the
+    /// database, ordered by version and followed by the repeatable ones.
+    /// Only reads the bookkeeping table.
+    pub async fn status<DB: Backend>(
+        &self,
+        db: &Pool<DB>,
+    ) -> Result<Vec<MigrationStatus>, MigrationError> {
+        self.collect_status::<DB>(db, false).await
+    }
+
+    /// Like [`status`](Migrator::status), but also lists the migrations of
+    /// every other namespace sharing the bookkeeping table, ordered by
+    /// namespace and version. As their files are not known to this migrator,
+    /// those are only reported as applied or partially applied.
+    pub async fn status_all<DB: Backend>(
+        &self,
+        db: &Pool<DB>,
+    ) -> Result<Vec<MigrationStatus>, MigrationError> {
+        self.collect_status::<DB>(db, true).await
+    }
+
+    async fn collect_status<DB: Backend>(
+        &self,
+        db: &Pool<DB>,
+        all_namespaces: bool,
+    ) -> Result<Vec<MigrationStatus>, MigrationError> {
+        let dialect = self.dialect_for::<DB>()?;
+        let mut conn = db.acquire().await?;
+
+        let (current, others): (Vec<AppliedMigration>, Vec<AppliedMigration>) =
+            if self.table_exists::<DB>(&mut conn, dialect).await? {
+                let select = self.select_outdated::<DB>(&mut conn, dialect).await?;
+                DB::fetch_applied(&mut conn, &select)
+                    .await?
+                    .into_iter()
+                    .partition(|a| a.namespace == self.namespace)
+            } else {
+                (vec![], vec![])
+            };
+
+        let mut status: Vec<MigrationStatus> = self
+            .migrations
+            .iter()
+            .map(|migration| {
+                let applied = current.iter().find(|a| a.version == migration.version);
+
+                let state = match applied {
+                    None => MigrationState::Pending,
+                    Some(a) if !a.success => MigrationState::PartiallyApplied,
+                    Some(a) if a.checksum != migration.checksum && migration.repeatable => {
+                        MigrationState::Pending
                     }
+                    Some(a) if a.checksum != migration.checksum => MigrationState::Modified,
+                    Some(_) => MigrationState::Applied,
+                };
+
+                MigrationStatus {
+                    applied_at: applied.map(|a| a.applied_at.clone()),
+                    checksum: migration.checksum.clone(),
+                    execution_time: applied.map(AppliedMigration::execution_time),
+                    name: migration.name.clone(),
+                    namespace: self.namespace.clone(),
+                    repeatable: migration.repeatable,
+                    state,
+                    version: migration.version,
                 }
+            })
+            .collect();
+
+        for a in self.missing_migrations(&current) {
+            status.push(a.status(if a.success {
+                MigrationState::MissingLocally
+            } else {
+                MigrationState::PartiallyApplied
+            }));
+        }
+
+        if all_namespaces {
+            for a in &others {
+                status.push(a.status(if a.success {
+                    MigrationState::Applied
+                } else {
+                    MigrationState::PartiallyApplied
+                }));
+            }
+        }
+
+        status.sort_by(|a, b| (&a.namespace, a.sort_key()).cmp(&(&b.namespace, b.sort_key())));
+
+        Ok(status)
+    }
+
+    fn dialect_for<DB: Backend>(&self) -> Result<&dyn Dialect, MigrationError> {
+        match &self.dialect {
+            Some(dialect) => Ok(dialect.as_ref()),
+            None => dialect::for_database::<DB>().ok_or(MigrationError::DialectError),
+        }
+    }
+
+    async fn run<DB: Backend>(
+        &self,
+        db: &Pool<DB>,
+        target: Target,
+        wait: bool,
+    ) -> Result<bool, MigrationError> {
+        let dialect = self.dialect_for::<DB>()?;
+
+        for migration in &self.migrations {
+            if let Some(step) = &migration.step {
+                if step.get::<DB::Connection>().is_none() {
+                    return Err(MigrationError::StepError(migration.version));
+                }
+            }
+        }
+
+        let mut conn = db.acquire().await?;
+
+        if !self.lock::<DB>(&mut conn, dialect, wait).await? {
+            return Ok(false);
+        }
+
+        let result = self.run_locked::<DB>(&mut conn, dialect, target).await;
+
+        let unlocked = match dialect.unlock(self.lock_key()) {
+            Some(unlock) => DB::execute(&mut conn, &unlock, vec![]).await,
+            None => Ok(()),
+        };
+
+        result?;
+        unlocked?;
+
+        Ok(true)
+    }
+
+    /// The quoted and schema-qualified name of the bookkeeping table.
+    fn table_name(&self, dialect: &dyn Dialect) -> String {
+        let table = dialect.quote_identifier(&self.table);
+
+        match &self.schema {
+            Some(schema) => format!("{}.{}", dialect.quote_identifier(schema), table),
+            None => table,
+        }
+    }
+
+    /// Key of the advisory lock, derived from the bookkeeping table so that
+    /// migrators sharing a table exclude each other.
+    fn lock_key(&self) -> i64 {
+        let name = match &self.schema {
+            Some(schema) => format!("{}.{}", schema, self.table),
+            None => self.table.clone(),
+        };
+
+        let digest = Sha256::digest(name.as_bytes());
+        i64::from_be_bytes(digest[..8].try_into().unwrap())
+    }
+
+    async fn lock<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        wait: bool,
+    ) -> Result<bool, MigrationError> {
+        let key = self.lock_key();
+
+        if wait && self.lock_timeout.is_none() {
+            if let Some(lock) = dialect.lock(key) {
+                DB::execute(conn, &lock, vec![]).await?;
+                return Ok(true);
+            }
+        }
+
+        let try_lock = match dialect.try_lock(key) {
+            Some(try_lock) => try_lock,
+            None => return Ok(true),
+        };
+
+        let deadline = self.lock_timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            if DB::fetch_exists(conn, &try_lock).await? {
+                return Ok(true);
+            }
+
+            if !wait {
+                return Ok(false);
+            }
+
+            if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                return Err(MigrationError::LockError);
+            }
+
+            sqlx_rt::sleep(LOCK_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn run_locked<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        target: Target,
+    ) -> Result<(), MigrationError> {
+        if let Some(schema) = &self.schema {
+            let exists = match dialect.select_schema(schema) {
+                Some(select) => DB::fetch_exists(conn, &select).await?,
+                None => false,
             };
+
+            if !exists {
+                if let Some(create) = dialect.create_schema(&dialect.quote_identifier(schema)) {
+                    DB::execute(conn, &create, vec![]).await?;
+                }
+            }
         }
 
-        Ok(())
+        DB::execute(
+            conn,
+            &dialect.create_table(&self.table_name(dialect)),
+            vec![],
+        )
+        .await?;
+
+        self.upgrade_table::<DB>(conn, dialect).await?;
+
+        let current = self
+            .get_applied_migrations::<DB>(
+                conn,
+                &dialect.select_migrations(&self.table_name(dialect)),
+            )
+            .await?;
+
+        self.check_missing(&current)?;
+
+        match target {
+            Target::Latest => {
+                self.check_out_of_order(&current, None)?;
+                self.apply_pending::<DB>(conn, dialect, &current, None)
+                    .await
+            }
+            Target::Version(version) => {
+                self.check_out_of_order(&current, Some(version))?;
+
+                let revert: Vec<&AppliedMigration> = current
+                    .iter()
+                    .rev()
+                    .filter(|a| a.version > version && !a.is_repeatable())
+                    .collect();
+
+                self.revert_applied::<DB>(conn, dialect, &revert).await?;
+                self.apply_pending::<DB>(conn, dialect, &current, Some(version))
+                    .await
+            }
+            Target::Rollback(steps) => {
+                let revert: Vec<&AppliedMigration> = current
+                    .iter()
+                    .rev()
+                    .filter(|a| !a.is_repeatable())
+                    .take(steps)
+                    .collect();
+
+                self.revert_applied::<DB>(conn, dialect, &revert).await
+            }
+        }
+    }
+
+    /// Applied migrations that are not known locally.
+    fn missing_migrations<'a>(
+        &'a self,
+        current: &'a [AppliedMigration],
+    ) -> impl Iterator<Item = &'a AppliedMigration> + 'a {
+        current
+            .iter()
+            .filter(move |a| !self.migrations.iter().any(|m| m.version == a.version))
     }
 
-    async fn ensure_table(&self, db: &PgPool) -> Result<PgQueryResult, sqlx::Error> {
-        db.execute(
-            r#"
-                CREATE TABLE IF NOT EXISTS migrations (
-                    version     BIGINT PRIMARY KEY,
-                    name        TEXT NOT NULL,
-                    checksum    VARCHAR(64),
-                    created_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+    fn check_missing(&self, current: &[AppliedMigration]) -> Result<(), MigrationError> {
+        let missing: Vec<String> = self
+            .missing_migrations(current)
+            .map(AppliedMigration::label)
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        match self.missing_policy {
+            MissingPolicy::Error => Err(MigrationError::MissingError(missing)),
+            MissingPolicy::Warn => {
+                log::warn!(
+                    "Applied migrations are missing locally: {}",
+                    missing.join(", ")
                 );
-            "#,
-        )
-        .await
+                Ok(())
+            }
+            MissingPolicy::Ignore => Ok(()),
+        }
+    }
+
+    /// The latest version that stays applied when migrating to `target`, and
+    /// the pending migrations older than it.
+    fn out_of_order_versions(
+        &self,
+        current: &[AppliedMigration],
+        target: Option<i64>,
+    ) -> Option<(i64, Vec<i64>)> {
+        let below_target = |version: i64| target.map_or(true, |t| version <= t);
+
+        let latest = current
+            .iter()
+            .filter(|a| !a.is_repeatable())
+            .map(|a| a.version)
+            .filter(|v| below_target(*v))
+            .max()?;
+
+        let offending = self
+            .migrations
+            .iter()
+            .filter(|m| !m.repeatable)
+            .map(|m| m.version)
+            .filter(|v| *v < latest && !current.iter().any(|a| a.version == *v))
+            .collect();
+
+        Some((latest, offending))
+    }
+
+    /// Checks the migrations that would be applied up to `target` against the
+    /// latest version that stays applied.
+    fn check_out_of_order(
+        &self,
+        current: &[AppliedMigration],
+        target: Option<i64>,
+    ) -> Result<(), MigrationError> {
+        let (latest, offending) = match self.out_of_order_versions(current, target) {
+            Some((latest, offending)) if !offending.is_empty() => (latest, offending),
+            _ => return Ok(()),
+        };
+
+        match self.out_of_order {
+            OutOfOrder::Allow => Ok(()),
+            OutOfOrder::Warn => {
+                log::warn!(
+                    "Applying migrations older than the latest applied one ({}): {:?}",
+                    latest,
+                    offending
+                );
+                Ok(())
+            }
+            OutOfOrder::Reject => Err(MigrationError::OutOfOrderError(offending)),
+        }
+    }
+
+    /// Brings a bookkeeping table created by an older version up to date.
+    async fn upgrade_table<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+    ) -> Result<(), MigrationError> {
+        let upgrades = dialect.upgrade_table(
+            &self.table_name(dialect),
+            self.schema.as_deref(),
+            &self.table,
+        );
+
+        for upgrade in upgrades {
+            if DB::fetch_exists(conn, &upgrade.check).await? {
+                continue;
+            }
+
+            let mut tx = conn.begin().await?;
+
+            for statement in &upgrade.statements {
+                DB::execute(&mut tx, statement, vec![]).await?;
+            }
+
+            tx.commit().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn table_exists<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+    ) -> Result<bool, MigrationError> {
+        match dialect.select_table(self.schema.as_deref(), &self.table) {
+            Some(select) => Ok(DB::fetch_exists(conn, &select).await?),
+            None => Ok(true),
+        }
     }
 
-    async fn get_applied_migrations(
+    /// Selects the applied migrations from a bookkeeping table that may not
+    /// have been upgraded yet, without changing it.
+    async fn select_outdated<DB: Backend>(
         &self,
-        db: &PgPool,
-    ) -> Result<Vec<AppliedMigration>, sqlx::Error> {
-        let mut result: Vec<AppliedMigration> = vec![];
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+    ) -> Result<String, MigrationError> {
+        let table = self.table_name(dialect);
+        let mut defaults = vec![];
 
-        db.fetch_all(
-            r#"
-                SELECT version, checksum
-                FROM migrations
-                ORDER BY version
-            "#,
+        for upgrade in dialect.upgrade_table(&table, self.schema.as_deref(), &self.table) {
+            if !DB::fetch_exists(conn, &upgrade.check).await? {
+                defaults.extend(upgrade.defaults);
+            }
+        }
+
+        if defaults.is_empty() {
+            return Ok(dialect.select_migrations(&table));
+        }
+
+        Ok(dialect.select_migrations(&format!(
+            "(SELECT *, {} FROM {}) AS outdated",
+            defaults.join(", "),
+            table
+        )))
+    }
+
+    async fn get_applied_migrations<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        select: &str,
+    ) -> Result<Vec<AppliedMigration>, MigrationError> {
+        let current: Vec<AppliedMigration> = DB::fetch_applied(conn, select)
+            .await?
+            .into_iter()
+            .filter(|a| a.namespace == self.namespace)
+            .collect();
+
+        let partial: Vec<i64> = current
+            .iter()
+            .filter(|a| !a.success)
+            .map(|a| a.version)
+            .collect();
+
+        if !partial.is_empty() {
+            return Err(MigrationError::PartialError(partial));
+        }
+
+        Ok(current)
+    }
+
+    async fn apply_pending<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        current: &[AppliedMigration],
+        target: Option<i64>,
+    ) -> Result<(), MigrationError> {
+        for migration in self.migrations.iter().filter(|m| !m.repeatable) {
+            if target.map_or(false, |t| migration.version > t) {
+                break;
+            }
+
+            match current.iter().find(|a| a.version == migration.version) {
+                None => {
+                    self.apply_migration::<DB>(conn, dialect, migration, false)
+                        .await?
+                }
+                Some(a) => check_checksum(migration, a)?,
+            };
+        }
+
+        // Repeatable migrations may depend on any versioned one, so they are
+        // only run once the latest version is applied.
+        if target.is_some() {
+            return Ok(());
+        }
+
+        for migration in self.migrations.iter().filter(|m| m.repeatable) {
+            match current.iter().find(|a| a.version == migration.version) {
+                None => {
+                    self.apply_migration::<DB>(conn, dialect, migration, false)
+                        .await?
+                }
+                Some(a) if a.checksum != migration.checksum => {
+                    self.apply_migration::<DB>(conn, dialect, migration, true)
+                        .await?
+                }
+                Some(_) => {}
+            };
+        }
+
+        Ok(())
+    }
+
+    async fn revert_applied<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        applied: &[&AppliedMigration],
+    ) -> Result<(), MigrationError> {
+        let mut revert: Vec<&Migration> = vec![];
+
+        for a in applied {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|m| m.version == a.version)
+                .ok_or(MigrationError::IrreversibleError(a.version))?;
+
+            check_checksum(migration, a)?;
+
+            if migration.down_sql.is_none() {
+                return Err(MigrationError::IrreversibleError(a.version));
+            }
+
+            revert.push(migration);
+        }
+
+        for migration in revert {
+            self.revert_migration::<DB>(conn, dialect, migration)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn apply_migration<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        migration: &Migration,
+        replace: bool,
+    ) -> Result<(), MigrationError> {
+        if !dialect.transactional_ddl() {
+            if replace {
+                self.delete_migration::<DB>(conn, dialect, migration)
+                    .await?;
+            }
+
+            self.insert_migration::<DB>(conn, dialect, migration, Duration::default())
+                .await?;
+        }
+
+        let started = Instant::now();
+
+        if migration.no_transaction {
+            self.execute_up::<DB>(conn, dialect, migration).await?;
+            self.finish_migration::<DB>(conn, dialect, migration, replace, started.elapsed())
+                .await?;
+        } else {
+            let mut tx = conn.begin().await?;
+
+            self.execute_up::<DB>(&mut tx, dialect, migration).await?;
+            self.finish_migration::<DB>(&mut tx, dialect, migration, replace, started.elapsed())
+                .await?;
+
+            tx.commit().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn insert_migration<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        migration: &Migration,
+        execution_time: Duration,
+    ) -> Result<(), MigrationError> {
+        DB::execute(
+            conn,
+            &dialect.insert_migration(&self.table_name(dialect)),
+            vec![
+                Argument::Int(migration.version),
+                Argument::Text(migration.name.clone()),
+                Argument::Text(migration.checksum.clone()),
+                Argument::Int(execution_time.as_millis() as i64),
+                Argument::NullableText(migration.source.clone()),
+                Argument::Text(self.namespace.clone()),
+            ],
         )
-        .await?
-        .iter()
-        .try_for_each(|row: &PgRow| -> Result<(), sqlx::Error> {
-            result.push(AppliedMigration {
-                checksum: row.try_get("checksum")?,
-                version: row.try_get("version")?,
-            });
+        .await?;
 
-            Ok(())
-        })?;
+        Ok(())
+    }
 
-        Ok(result)
+    async fn finish_migration<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        migration: &Migration,
+        replace: bool,
+        execution_time: Duration,
+    ) -> Result<(), MigrationError> {
+        if dialect.transactional_ddl() {
+            if replace {
+                self.delete_migration::<DB>(conn, dialect, migration)
+                    .await?;
+            }
+
+            self.insert_migration::<DB>(conn, dialect, migration, execution_time)
+                .await
+        } else {
+            self.set_success::<DB>(conn, dialect, migration.version, true, execution_time)
+                .await
+        }
+    }
+
+    async fn revert_migration<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        migration: &Migration,
+    ) -> Result<(), MigrationError> {
+        if !dialect.transactional_ddl() {
+            self.set_success::<DB>(conn, dialect, migration.version, false, Duration::default())
+                .await?;
+        }
+
+        let down_sql = migration.down_sql.as_deref().unwrap_or_default();
+
+        if migration.no_transaction {
+            self.execute_sql::<DB>(conn, dialect, migration, down_sql)
+                .await?;
+            self.delete_migration::<DB>(conn, dialect, migration)
+                .await?;
+        } else {
+            let mut tx = conn.begin().await?;
+
+            self.execute_sql::<DB>(&mut tx, dialect, migration, down_sql)
+                .await?;
+            self.delete_migration::<DB>(&mut tx, dialect, migration)
+                .await?;
+
+            tx.commit().await?;
+        }
+
+        Ok(())
     }
 
-    async fn apply_migration(&self, db: &PgPool, migration: &Migration) -> Result<(), sqlx::Error> {
-        let mut tx = db.begin().await?;
+    async fn delete_migration<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        migration: &Migration,
+    ) -> Result<(), MigrationError> {
+        DB::execute(
+            conn,
+            &dialect.delete_migration(&self.table_name(dialect)),
+            vec![
+                Argument::Int(migration.version),
+                Argument::Text(self.namespace.clone()),
+            ],
+        )
+        .await?;
 
-        for stmt in migration.sql.split(";") {
-            if !stmt.trim().is_empty() {
-                tx.execute(sqlx::query(&stmt)).await?;
+        Ok(())
+    }
+
+    async fn execute_up<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        migration: &Migration,
+    ) -> Result<(), MigrationError> {
+        match migration
+            .step
+            .as_ref()
+            .and_then(Step::get::<DB::Connection>)
+        {
+            Some(step) => step.run(conn).await,
+            None => {
+                self.execute_sql::<DB>(conn, dialect, migration, &migration.sql)
+                    .await
             }
         }
+    }
+
+    async fn execute_sql<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        migration: &Migration,
+        sql: &str,
+    ) -> Result<(), MigrationError> {
+        let sql = template::substitute(sql, &self.vars, migration.version)?;
+        let sql = sql.as_ref();
 
-        sqlx::query(
-            r#"
-                INSERT INTO migrations ( version, name, checksum )
-                VALUES ($1, $2, $3)
-            "#,
+        let failed = |index: usize, stmt: &Statement<'_>, source| MigrationError::StatementError {
+            version: migration.version,
+            index,
+            line: stmt.line,
+            sql: stmt.sql.to_owned(),
+            source,
+        };
+
+        if self.batch || migration.batch {
+            let stmt = Statement { sql, line: 1 };
+
+            return DB::execute_batch(conn, sql)
+                .await
+                .map_err(|source| failed(1, &stmt, source));
+        }
+
+        for (i, stmt) in dialect.split_statements(sql).iter().enumerate() {
+            DB::execute(conn, stmt.sql, vec![])
+                .await
+                .map_err(|source| failed(i + 1, stmt, source))?;
+        }
+
+        Ok(())
+    }
+
+    async fn set_success<DB: Backend>(
+        &self,
+        conn: &mut DB::Connection,
+        dialect: &dyn Dialect,
+        version: i64,
+        success: bool,
+        execution_time: Duration,
+    ) -> Result<(), MigrationError> {
+        let update = dialect
+            .update_migration(&self.table_name(dialect))
+            .ok_or(MigrationError::DialectError)?;
+
+        DB::execute(
+            conn,
+            &update,
+            vec![
+                Argument::Bool(success),
+                Argument::Int(execution_time.as_millis() as i64),
+                Argument::Int(version),
+                Argument::Text(self.namespace.clone()),
+            ],
         )
-        .bind(migration.version)
-        .bind(&*migration.name)
-        .bind(&*migration.checksum)
-        .execute(&mut tx)
         .await?;
 
-        tx.commit().await
+        Ok(())
+    }
+}
+
+fn check_checksum(migration: &Migration, applied: &AppliedMigration) -> Result<(), MigrationError> {
+    if migration.checksum == applied.checksum {
+        return Ok(());
     }
+
+    Err(MigrationError::ChecksumError {
+        version: migration.version,
+        name: migration.name.clone(),
+        expected: applied.checksum.clone(),
+        actual: migration.checksum.clone(),
+    })
 }
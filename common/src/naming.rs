@@ -0,0 +1,117 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref DEFAULT_REGEX: Regex = Regex::new(
+        r"^(?P<version>[0-9]+)_(?P<name>[a-z_]+)(\.(?P<direction>up|down))?(?P<notx>\.notx)?\.sql$"
+    )
+    .unwrap();
+    static ref RELAXED_REGEX: Regex = Regex::new(
+        r"^(?P<version>[0-9]+)_(?P<name>[A-Za-z0-9_-]+)(\.(?P<direction>up|down))?(?P<notx>\.notx)?\.sql$"
+    )
+    .unwrap();
+    static ref FLYWAY_REGEX: Regex = Regex::new(
+        r"^(?P<prefix>[VU])(?P<version>[0-9]+)__(?P<name>[A-Za-z0-9_-]+)(?P<notx>\.notx)?\.sql$"
+    )
+    .unwrap();
+    static ref SQLX_CLI_REGEX: Regex = Regex::new(
+        r"^(?P<version>[0-9]+)_(?P<name>[^.]+)(\.(?P<direction>up|down))?(?P<notx>\.notx)?\.sql$"
+    )
+    .unwrap();
+    static ref REPEATABLE_REGEX: Regex =
+        Regex::new(r"^R__(?P<name>[A-Za-z0-9_-]+)(?P<notx>\.notx)?\.sql$").unwrap();
+}
+
+/// How migration files are named.
+///
+/// Every scheme accepts a `.notx` suffix right before the `.sql` extension to
+/// run the migration outside of a transaction, and Flyway's
+/// `R__<name>.sql` for [repeatable](crate::Migration::repeatable) migrations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NamingScheme {
+    /// `<version>_<name>.sql`, where the name consists of lowercase letters
+    /// and underscores. Down migrations end in `.down.sql`.
+    Default,
+    /// Like [`Default`](NamingScheme::Default), but the name may contain
+    /// letters of either case, digits, underscores and hyphens.
+    Relaxed,
+    /// Flyway's `V<version>__<name>.sql`, with down migrations named
+    /// `U<version>__<name>.sql`. Names are as in
+    /// [`Relaxed`](NamingScheme::Relaxed).
+    Flyway,
+    /// sqlx-cli's `<version>_<description>.sql`, where the description can be
+    /// anything without a dot. Down migrations end in `.down.sql`.
+    SqlxCli,
+}
+
+impl Default for NamingScheme {
+    fn default() -> Self {
+        NamingScheme::Default
+    }
+}
+
+impl NamingScheme {
+    /// Has the groups `version`, `name` and `notx`, and `direction` (`up` or
+    /// `down`) or `prefix` (`V` or `U`) for the direction.
+    pub(crate) fn regex(&self) -> &'static Regex {
+        match self {
+            NamingScheme::Default => &DEFAULT_REGEX,
+            NamingScheme::Relaxed => &RELAXED_REGEX,
+            NamingScheme::Flyway => &FLYWAY_REGEX,
+            NamingScheme::SqlxCli => &SQLX_CLI_REGEX,
+        }
+    }
+
+    /// Matches repeatable migrations, with the groups `name` and `notx`.
+    pub(crate) fn repeatable_regex() -> &'static Regex {
+        &REPEATABLE_REGEX
+    }
+
+    /// Explains why `file_name` does not match the [`regex`](Self::regex).
+    pub(crate) fn mismatch_reason(&self, file_name: &str) -> &'static str {
+        let stem = match file_name.strip_suffix(".sql") {
+            Some(stem) => stem,
+            None => return "expected the `.sql` extension",
+        };
+
+        let stem = stem.strip_suffix(".notx").unwrap_or(stem);
+
+        let (stem, separator) = if *self == NamingScheme::Flyway {
+            match stem.strip_prefix('V').or_else(|| stem.strip_prefix('U')) {
+                Some(stem) => (stem, "__"),
+                None => return "expected a `V` or `U` prefix",
+            }
+        } else {
+            let stem = stem
+                .strip_suffix(".up")
+                .or_else(|| stem.strip_suffix(".down"))
+                .unwrap_or(stem);
+
+            (stem, "_")
+        };
+
+        let name = stem.trim_start_matches(|c: char| c.is_ascii_digit());
+
+        if name.len() == stem.len() {
+            "expected a numeric version"
+        } else if !name.starts_with(separator) {
+            if separator == "__" {
+                "expected `__` between the version and the name"
+            } else {
+                "expected `_` between the version and the name"
+            }
+        } else if name.len() == separator.len() {
+            "expected a name after the version"
+        } else {
+            match self {
+                NamingScheme::Default => {
+                    "the name may only contain lowercase letters and underscores"
+                }
+                NamingScheme::Relaxed | NamingScheme::Flyway => {
+                    "the name may only contain letters, digits, underscores and hyphens"
+                }
+                NamingScheme::SqlxCli => "the name may not contain dots",
+            }
+        }
+    }
+}
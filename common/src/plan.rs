@@ -0,0 +1,99 @@
+use crate::{sort_key, MissingPolicy, OutOfOrder};
+use std::fmt;
+
+/// What [`Migrator::migrate`] would do, as computed by [`Migrator::plan`].
+///
+/// [`Migrator::migrate`]: crate::Migrator::migrate
+/// [`Migrator::plan`]: crate::Migrator::plan
+#[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Plan {
+    /// Migrations that are applied already and unchanged.
+    pub applied: Vec<PlannedMigration>,
+    /// Migrations that would be applied, in order.
+    pub pending: Vec<PlannedMigration>,
+    /// Applied migrations whose file changed since. These make `migrate` fail.
+    pub modified: Vec<ModifiedMigration>,
+    /// Applied migrations the migrator does not know. These make `migrate`
+    /// fail unless the [`MissingPolicy`] allows them.
+    pub missing: Vec<PlannedMigration>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) missing_policy: MissingPolicy,
+    /// Versions of pending migrations that are older than the latest applied
+    /// one. These make `migrate` fail with [`OutOfOrder::Reject`].
+    pub out_of_order: Vec<i64>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) out_of_order_policy: OutOfOrder,
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PlannedMigration {
+    pub name: String,
+    /// Whether this is a [repeatable](crate::Migration::repeatable) migration.
+    pub repeatable: bool,
+    pub version: i64,
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ModifiedMigration {
+    /// Checksum recorded when the migration was applied.
+    pub applied_checksum: String,
+    /// Checksum of the migration as it is now.
+    pub checksum: String,
+    pub name: String,
+    pub version: i64,
+}
+
+impl Plan {
+    /// Whether `migrate` would neither apply anything nor fail.
+    pub fn is_up_to_date(&self) -> bool {
+        self.pending.is_empty() && !self.would_fail()
+    }
+
+    /// Whether `migrate` would fail before applying anything, on a modified
+    /// migration or on a missing or out-of-order one rejected by the
+    /// [`MissingPolicy`] or [`OutOfOrder`] policy.
+    pub fn would_fail(&self) -> bool {
+        !self.modified.is_empty()
+            || !self.missing.is_empty() && self.missing_policy == MissingPolicy::Error
+            || !self.out_of_order.is_empty() && self.out_of_order_policy == OutOfOrder::Reject
+    }
+}
+
+impl fmt::Display for Plan {
+    /// One line per migration, in the order they run. Repeatable migrations
+    /// are listed last and named `R__<name>`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines: Vec<(bool, i64, &str, &str)> = vec![];
+
+        for (state, planned) in [
+            ("applied", &self.applied),
+            ("pending", &self.pending),
+            ("missing", &self.missing),
+        ] {
+            lines.extend(
+                planned
+                    .iter()
+                    .map(|m| (m.repeatable, m.version, state, &*m.name)),
+            );
+        }
+        lines.extend(
+            self.modified
+                .iter()
+                .map(|m| (false, m.version, "modified", &*m.name)),
+        );
+        lines.sort_by_key(|&(repeatable, version, _, name)| sort_key(repeatable, version, name));
+
+        for (repeatable, version, state, name) in lines {
+            if repeatable {
+                writeln!(f, "{:<8} R__{}", state, name)?;
+            } else {
+                writeln!(f, "{:<8} {} {}", state, version, name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+/// What to do about migrations recorded in the database that the migrator
+/// does not know, e.g. after a file was deleted or renamed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MissingPolicy {
+    /// Fail with [`MigrationError::MissingError`] before changing anything.
+    ///
+    /// [`MigrationError::MissingError`]: crate::MigrationError::MissingError
+    Error,
+    /// Log a warning and carry on.
+    Warn,
+    /// Carry on silently.
+    Ignore,
+}
+
+impl Default for MissingPolicy {
+    fn default() -> Self {
+        MissingPolicy::Error
+    }
+}
+
+/// What to do about pending migrations that are older than the latest applied
+/// one, e.g. after merging a branch with an older timestamp.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutOfOrder {
+    /// Apply them silently.
+    Allow,
+    /// Log a warning and apply them.
+    Warn,
+    /// Fail with [`MigrationError::OutOfOrderError`] before changing anything.
+    ///
+    /// [`MigrationError::OutOfOrderError`]: crate::MigrationError::OutOfOrderError
+    Reject,
+}
+
+impl Default for OutOfOrder {
+    fn default() -> Self {
+        OutOfOrder::Allow
+    }
+}
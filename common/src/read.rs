@@ -0,0 +1,61 @@
+use crate::{MigrationError, NamingScheme};
+use std::fs::DirEntry;
+
+/// Which entries of a migrations directory are read as migrations.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOptions {
+    pub(crate) naming: NamingScheme,
+    strict: bool,
+}
+
+impl ReadOptions {
+    /// How migration files are named, [`NamingScheme::Default`] unless set.
+    pub fn naming(mut self, naming: NamingScheme) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Fails on every entry that is not a migration instead of skipping
+    /// subdirectories, dotfiles and files without the `.sql` extension.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Whether `entry` should be read as a migration.
+    ///
+    /// Files that look like a migration but have an invalid name are not
+    /// skipped, so that they fail to parse instead of being silently ignored.
+    pub(crate) fn includes(&self, entry: &DirEntry) -> Result<bool, MigrationError> {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+
+        let is_dir = entry
+            .file_type()
+            .map_err(|source| MigrationError::ReadError {
+                filename: file_name.clone(),
+                source,
+            })?
+            .is_dir();
+
+        let is_sql = file_name
+            .rsplit_once('.')
+            .map_or(false, |(_, ext)| ext.eq_ignore_ascii_case("sql"));
+
+        if !is_dir && is_sql && !file_name.starts_with('.') {
+            return Ok(true);
+        }
+
+        if !self.strict {
+            return Ok(false);
+        }
+
+        Err(MigrationError::FilenameError {
+            filename: file_name,
+            reason: String::from(if is_dir {
+                "subdirectories are not allowed in strict mode"
+            } else {
+                "only migrations are allowed in strict mode"
+            }),
+        })
+    }
+}
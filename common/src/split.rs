@@ -0,0 +1,321 @@
+/// A single statement of a migration file.
+#[derive(Debug, PartialEq)]
+pub struct Statement<'a> {
+    /// The statement text, without the terminating semicolon.
+    pub sql: &'a str,
+    /// The line the statement starts on, counting from 1.
+    pub line: usize,
+}
+
+/// The lexical rules [`split_statements_for`] follows, which differ between
+/// databases.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Syntax {
+    /// `E''` escape strings, dollar-quoted bodies, nested block comments and
+    /// `BEGIN ATOMIC ... END` bodies of functions and procedures.
+    Postgres,
+    /// Backslash escapes in strings, `#` line comments, backquoted identifiers
+    /// and `BEGIN ... END` bodies of stored procedures, functions, triggers and
+    /// events.
+    MySql,
+    /// `BEGIN ... END` bodies of `CREATE TRIGGER` statements, and backquoted
+    /// and bracketed identifiers.
+    Sqlite,
+}
+
+/// Splits SQL into statements on every top-level semicolon, following
+/// PostgreSQL's syntax.
+///
+/// Semicolons inside single- and double-quoted strings, `E''` escape strings,
+/// dollar-quoted bodies (`$$ ... $$`, `$tag$ ... $tag$`), `BEGIN ATOMIC ... END`
+/// bodies, line comments and (nested) block comments are not treated as
+/// separators. Chunks containing nothing but whitespace and comments are
+/// dropped.
+pub fn split_statements(sql: &str) -> Vec<Statement<'_>> {
+    split_statements_for(sql, Syntax::Postgres)
+}
+
+/// Like [`split_statements`], but with the quoting and comment rules of the
+/// given [`Syntax`].
+pub fn split_statements_for(sql: &str, syntax: Syntax) -> Vec<Statement<'_>> {
+    let bytes = sql.as_bytes();
+    let mut statements = vec![];
+
+    let mut line = 1;
+    let mut start: Option<(usize, usize)> = None;
+    let mut has_code = false;
+    let mut body = Body::new(syntax);
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+
+        if start.is_none() && !c.is_ascii_whitespace() {
+            start = Some((i, line));
+        }
+
+        let end = match c {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => find(bytes, i, b"\n").unwrap_or(bytes.len()),
+            b'#' if syntax == Syntax::MySql => find(bytes, i, b"\n").unwrap_or(bytes.len()),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                block_comment_end(bytes, i, syntax == Syntax::Postgres)
+            }
+            b';' if body.is_open() => {
+                has_code = true;
+                i + 1
+            }
+            b';' => {
+                if let Some((from, from_line)) = start.take() {
+                    if has_code {
+                        statements.push(Statement {
+                            sql: sql[from..i].trim_end(),
+                            line: from_line,
+                        });
+                    }
+                }
+
+                has_code = false;
+                body = Body::new(syntax);
+                i + 1
+            }
+            _ => {
+                has_code = has_code || !c.is_ascii_whitespace();
+
+                match (c, syntax) {
+                    (b'\'', Syntax::Postgres) if is_escape_string(bytes, i) => {
+                        quoted_end(bytes, i, b'\'', true)
+                    }
+                    (b'\'', Syntax::MySql) | (b'"', Syntax::MySql) => quoted_end(bytes, i, c, true),
+                    (b'\'', _) | (b'"', _) => quoted_end(bytes, i, c, false),
+                    (b'`', Syntax::MySql) | (b'`', Syntax::Sqlite) => {
+                        quoted_end(bytes, i, c, false)
+                    }
+                    (b'[', Syntax::Sqlite) => quoted_end(bytes, i, b']', false),
+                    (b'$', Syntax::Postgres) => dollar_quoted_end(bytes, i).unwrap_or(i + 1),
+                    _ if is_word_start(bytes, i) => {
+                        let end = word_end(bytes, i);
+                        body.word(&sql[i..end]);
+                        end
+                    }
+                    _ => i + 1,
+                }
+            }
+        };
+
+        line += bytes[i..end].iter().filter(|&&b| b == b'\n').count();
+        i = end;
+    }
+
+    if let Some((from, from_line)) = start {
+        if has_code {
+            statements.push(Statement {
+                sql: sql[from..].trim_end(),
+                line: from_line,
+            });
+        }
+    }
+
+    statements
+}
+
+/// Tracks whether a statement creates a routine or trigger with a body of
+/// several statements, and how deep it is nested in blocks, inside which
+/// semicolons do not end the statement.
+///
+/// These are `CREATE TRIGGER` in SQLite, `CREATE PROCEDURE`, `FUNCTION`,
+/// `TRIGGER` and `EVENT` in MySQL, and `CREATE FUNCTION` and `PROCEDURE` with
+/// a `BEGIN ATOMIC` body in PostgreSQL.
+struct Body {
+    syntax: Syntax,
+    kind: Kind,
+    /// Words of a MySQL `DEFINER` account that may still follow.
+    definer: usize,
+    depth: usize,
+    /// Whether the previous word was a PostgreSQL `BEGIN`, which only opens a
+    /// block if followed by `ATOMIC`.
+    after_begin: bool,
+    /// Whether the previous word was an `END` closing a block, which MySQL
+    /// may follow with the kind of block (`END IF`, `END LOOP`, ...).
+    after_end: bool,
+}
+
+impl Body {
+    fn new(syntax: Syntax) -> Self {
+        Self {
+            syntax,
+            kind: Kind::Empty,
+            definer: 0,
+            depth: 0,
+            after_begin: false,
+            after_end: false,
+        }
+    }
+
+    fn word(&mut self, word: &str) {
+        let is = |keyword: &str| word.eq_ignore_ascii_case(keyword);
+
+        match self.kind {
+            Kind::Empty if is("CREATE") => self.kind = Kind::Create,
+            Kind::Empty => self.kind = Kind::Other,
+            Kind::Create => self.header_word(word),
+            Kind::Routine => self.body_word(word),
+            Kind::Other => {}
+        }
+    }
+
+    fn body_word(&mut self, word: &str) {
+        let is = |keyword: &str| word.eq_ignore_ascii_case(keyword);
+        let after_begin = std::mem::take(&mut self.after_begin);
+        let after_end = std::mem::take(&mut self.after_end);
+
+        if is("BEGIN") && self.syntax == Syntax::Postgres {
+            self.after_begin = true;
+        } else if is("BEGIN") || is("ATOMIC") && after_begin || is("CASE") && !after_end {
+            self.depth += 1;
+        } else if is("END") {
+            self.after_end = self.depth > 0;
+            self.depth = self.depth.saturating_sub(1);
+        } else if after_end && (is("IF") || is("LOOP") || is("REPEAT") || is("WHILE")) {
+            // Only `BEGIN` and `CASE` are counted, so `END IF` and the like
+            // do not close a block after all.
+            self.depth += 1;
+        }
+    }
+
+    /// Looks for the kind of object created, skipping modifiers like
+    /// `OR REPLACE`.
+    fn header_word(&mut self, word: &str) {
+        let is = |keyword: &str| word.eq_ignore_ascii_case(keyword);
+
+        let (kinds, modifiers): (&[&str], &[&str]) = match self.syntax {
+            Syntax::Postgres => (&["FUNCTION", "PROCEDURE"], &["OR", "REPLACE"]),
+            Syntax::MySql => (
+                &["PROCEDURE", "FUNCTION", "TRIGGER", "EVENT"],
+                &["OR", "REPLACE", "AGGREGATE"],
+            ),
+            Syntax::Sqlite => (&["TRIGGER"], &["TEMP", "TEMPORARY"]),
+        };
+
+        if kinds.iter().any(|kind| is(kind)) {
+            self.kind = Kind::Routine;
+        } else if is("DEFINER") && self.syntax == Syntax::MySql {
+            // Followed by `user@host` or `CURRENT_USER`, unless quoted.
+            self.definer = 2;
+        } else if !modifiers.iter().any(|modifier| is(modifier)) {
+            if self.definer > 0 {
+                self.definer -= 1;
+            } else {
+                self.kind = Kind::Other;
+            }
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.depth > 0
+    }
+}
+
+/// What a statement turned out to be, as far as [`Body`] is concerned.
+enum Kind {
+    /// No word seen yet.
+    Empty,
+    /// A `CREATE` statement whose object type is still unknown.
+    Create,
+    /// Creates a routine or trigger that may have a body.
+    Routine,
+    Other,
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| from + p)
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+fn is_word_start(bytes: &[u8], i: usize) -> bool {
+    (bytes[i].is_ascii_alphabetic() || bytes[i] == b'_') && (i == 0 || !is_ident_byte(bytes[i - 1]))
+}
+
+fn word_end(bytes: &[u8], i: usize) -> usize {
+    bytes[i..]
+        .iter()
+        .position(|&b| !is_ident_byte(b))
+        .map_or(bytes.len(), |p| i + p)
+}
+
+/// Whether the quote at `i` opens an `E'...'` string with backslash escapes.
+fn is_escape_string(bytes: &[u8], i: usize) -> bool {
+    i > 0
+        && (bytes[i - 1] == b'E' || bytes[i - 1] == b'e')
+        && (i < 2 || !is_ident_byte(bytes[i - 2]))
+}
+
+/// Returns the index after the quote closing the string opened at `i`.
+fn quoted_end(bytes: &[u8], i: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut j = i + 1;
+
+    while j < bytes.len() {
+        if backslash_escapes && bytes[j] == b'\\' {
+            j += 2;
+        } else if bytes[j] == quote {
+            if bytes.get(j + 1) == Some(&quote) {
+                j += 2;
+            } else {
+                return j + 1;
+            }
+        } else {
+            j += 1;
+        }
+    }
+
+    bytes.len()
+}
+
+/// Returns the index after the `*/` closing the comment opened at `i`.
+fn block_comment_end(bytes: &[u8], i: usize, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut j = i;
+
+    while j < bytes.len() {
+        if bytes[j..].starts_with(b"/*") && (nested || depth == 0) {
+            depth += 1;
+            j += 2;
+        } else if bytes[j..].starts_with(b"*/") {
+            depth -= 1;
+            j += 2;
+
+            if depth == 0 {
+                return j;
+            }
+        } else {
+            j += 1;
+        }
+    }
+
+    bytes.len()
+}
+
+/// Returns the index after the closing tag of the dollar-quoted string opened
+/// at `i`, or `None` if the `$` does not open one (e.g. a `$1` parameter).
+fn dollar_quoted_end(bytes: &[u8], i: usize) -> Option<usize> {
+    if i > 0 && is_ident_byte(bytes[i - 1]) {
+        return None;
+    }
+
+    let tag_len = bytes[i + 1..]
+        .iter()
+        .position(|&b| b == b'$' || !(b.is_ascii_alphanumeric() || b == b'_'))?;
+
+    if bytes[i + 1 + tag_len] != b'$' || bytes.get(i + 1).map_or(false, u8::is_ascii_digit) {
+        return None;
+    }
+
+    let tag = &bytes[i..i + tag_len + 2];
+
+    Some(find(bytes, i + tag.len(), tag).map_or(bytes.len(), |end| end + tag.len()))
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+use std::time::Duration;
+
+/// A migration as reported by [`Migrator::status`].
+///
+/// [`Migrator::status`]: crate::Migrator::status
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MigrationStatus {
+    /// When the migration was applied, as reported by the database.
+    pub applied_at: Option<String>,
+    /// Checksum of the local migration, or the recorded one if it only exists
+    /// in the database.
+    pub checksum: String,
+    pub execution_time: Option<Duration>,
+    pub name: String,
+    /// The [namespace](crate::Migrator::namespace) the migration belongs to.
+    pub namespace: String,
+    /// Whether this is a [repeatable](crate::Migration::repeatable) migration.
+    pub repeatable: bool,
+    pub state: MigrationState,
+    pub version: i64,
+}
+
+impl MigrationStatus {
+    pub(crate) fn sort_key(&self) -> (bool, i64, &str) {
+        crate::sort_key(self.repeatable, self.version, &self.name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum MigrationState {
+    /// Applied and unchanged since.
+    Applied,
+    /// Not applied yet.
+    Pending,
+    /// Applied, but the local migration changed since.
+    Modified,
+    /// Applied, but not known to the migrator.
+    MissingLocally,
+    /// Started, but failed on a database without transactional DDL.
+    PartiallyApplied,
+}
+
+impl fmt::Display for MigrationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+            MigrationState::MissingLocally => "missing locally",
+            MigrationState::PartiallyApplied => "partially applied",
+        })
+    }
+}
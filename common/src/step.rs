@@ -0,0 +1,49 @@
+use crate::MigrationError;
+use futures_core::future::BoxFuture;
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// A migration written in Rust, for changes that cannot be expressed in SQL
+/// like backfills that need application code.
+///
+/// `C` is the connection type of the database, e.g. `PgConnection`. Closures
+/// can be added with [`Migrator::step_fn`](crate::Migrator::step_fn).
+pub trait MigrationStep<C>: Send + Sync + 'static {
+    /// Applies the migration inside its transaction.
+    fn run<'c>(&'c self, conn: &'c mut C) -> BoxFuture<'c, Result<(), MigrationError>>;
+}
+
+impl<C, F> MigrationStep<C> for F
+where
+    F: for<'c> Fn(&'c mut C) -> BoxFuture<'c, Result<(), MigrationError>> + Send + Sync + 'static,
+{
+    fn run<'c>(&'c self, conn: &'c mut C) -> BoxFuture<'c, Result<(), MigrationError>> {
+        self(conn)
+    }
+}
+
+/// A [`MigrationStep`] with its connection type erased, so that it fits into
+/// a [`Migration`](crate::Migration).
+#[derive(Clone)]
+pub struct Step(Arc<dyn Any + Send + Sync>);
+
+impl Step {
+    pub fn new<C: 'static, S: MigrationStep<C>>(step: S) -> Self {
+        let step: Arc<dyn MigrationStep<C>> = Arc::new(step);
+        Step(Arc::new(step))
+    }
+
+    /// The step, unless it was written for another database.
+    pub(crate) fn get<C: 'static>(&self) -> Option<&dyn MigrationStep<C>> {
+        self.0
+            .downcast_ref::<Arc<dyn MigrationStep<C>>>()
+            .map(|step| step.as_ref())
+    }
+}
+
+impl fmt::Debug for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Step")
+    }
+}
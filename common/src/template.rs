@@ -0,0 +1,31 @@
+use crate::MigrationError;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+lazy_static! {
+    static ref VARIABLE_REGEX: Regex =
+        Regex::new(r"\$\{(?P<name>[A-Za-z_][A-Za-z0-9_]*)\}").unwrap();
+}
+
+/// Replaces every `${name}` in the SQL of migration `version` with the value
+/// of `name` in `vars`.
+pub(crate) fn substitute<'a>(
+    sql: &'a str,
+    vars: &HashMap<String, String>,
+    version: i64,
+) -> Result<Cow<'a, str>, MigrationError> {
+    let undefined = VARIABLE_REGEX
+        .captures_iter(sql)
+        .find(|cap| !vars.contains_key(&cap["name"]));
+
+    if let Some(cap) = undefined {
+        return Err(MigrationError::VariableError {
+            version,
+            name: cap["name"].to_owned(),
+        });
+    }
+
+    Ok(VARIABLE_REGEX.replace_all(sql, |cap: &Captures| vars[&cap["name"]].clone()))
+}
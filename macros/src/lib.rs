@@ -1,29 +1,165 @@
-use fs::read_dir;
 use proc_macro::TokenStream;
+use proc_macro2::Span;
 use quote::quote;
-use sqlx_migrate_common::Migration;
-use std::{convert::TryInto, env, fs, path::Path};
-use syn::LitStr;
+use sqlx_migrate_common::{
+    collect_migrations, Migration, MigrationError, Migrator, NamingScheme, ReadOptions,
+};
+use std::{env, fs, io, path::Path};
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, LitBool, LitStr, Token,
+};
 
+struct Args {
+    dirs: Vec<LitStr>,
+    options: ReadOptions,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut dirs = vec![input.parse()?];
+        let mut options = ReadOptions::default();
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+
+            if input.is_empty() {
+                break;
+            }
+
+            if input.peek(LitStr) {
+                dirs.push(input.parse()?);
+                continue;
+            }
+
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "naming" => options = options.naming(parse_naming(input.parse()?)?),
+                "strict" => options = options.strict(input.parse::<LitBool>()?.value),
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("unknown option `{}`, expected `naming` or `strict`", key),
+                    ))
+                }
+            }
+        }
+
+        Ok(Args { dirs, options })
+    }
+}
+
+fn parse_naming(naming: LitStr) -> syn::Result<NamingScheme> {
+    match naming.value().as_str() {
+        "default" => Ok(NamingScheme::Default),
+        "relaxed" => Ok(NamingScheme::Relaxed),
+        "flyway" => Ok(NamingScheme::Flyway),
+        "sqlx-cli" => Ok(NamingScheme::SqlxCli),
+        _ => Err(syn::Error::new(
+            naming.span(),
+            "unknown naming scheme, expected one of \"default\", \"relaxed\", \"flyway\" or \"sqlx-cli\"",
+        )),
+    }
+}
+
+/// Embeds the migrations of one or more directories relative to the crate
+/// root, merged and ordered by version and followed by the repeatable
+/// `R__<name>.sql` migrations. Each migration records the directory it came
+/// from as its `source`.
+///
+/// Pass `naming = "relaxed"`, `"flyway"` or `"sqlx-cli"` to use another
+/// [`NamingScheme`] for the files. Pass `strict = true` to fail on any file in
+/// the directories that is not a migration instead of skipping it.
+///
+/// Changes to the files in the directories trigger a rebuild. Cargo cannot be
+/// told about new files from a macro though, so add a build script printing
+/// `cargo:rerun-if-changed=<directory>` to pick those up as well.
 #[proc_macro]
 pub fn embed(input: TokenStream) -> TokenStream {
-    let dir = syn::parse_macro_input!(input as LitStr);
-    let path = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join(&dir.value());
+    let Args { dirs, options } = syn::parse_macro_input!(input as Args);
+    let root = env::var("CARGO_MANIFEST_DIR").unwrap();
 
-    parse_dir(&path.to_str().unwrap()).into()
+    let mut errors = vec![];
+    let mut migrations = vec![];
+    let mut files = vec![];
+
+    // Reports every problem with the migrations, not just the first.
+    for dir in &dirs {
+        let path = Path::new(&root).join(&dir.value());
+
+        match parse_dir(&path, &options) {
+            Ok((dir_migrations, dir_files)) => {
+                migrations.extend(dir_migrations.into_iter().map(|mut migration| {
+                    migration.source = Some(dir.value());
+                    migration
+                }));
+                files.extend(dir_files);
+            }
+            Err(dir_errors) => errors.extend(
+                dir_errors
+                    .iter()
+                    .map(|err| syn::Error::new(dir.span(), err)),
+            ),
+        }
+    }
+
+    if errors.is_empty() {
+        match Migrator::try_new(migrations) {
+            Ok(migrator) => {
+                let migrations = migrator.migrations;
+
+                return quote! {
+                    {
+                        #( const _: &[u8] = include_bytes!(#files); )*
+
+                        sqlx_migrate::Migrator::new(
+                            vec![ #(#migrations),* ]
+                        )
+                    }
+                }
+                .into();
+            }
+            Err(err) => errors.push(syn::Error::new(Span::call_site(), err)),
+        }
+    }
+
+    let errors = errors.iter().map(syn::Error::to_compile_error);
+
+    // A block, so that several errors are valid in expression position.
+    quote!({ #(#errors)* }).into()
 }
 
-fn parse_dir(path: &str) -> proc_macro2::TokenStream {
-    let mut migrations: Vec<Migration> = read_dir(path)
-        .unwrap()
-        .map(|e| e.unwrap().try_into().unwrap())
-        .collect();
+fn parse_dir(
+    path: &Path,
+    options: &ReadOptions,
+) -> Result<(Vec<Migration>, Vec<String>), Vec<MigrationError>> {
+    let migrations = collect_migrations(path, options)?;
+    let files = tracked_files(path).map_err(|source| {
+        vec![MigrationError::ReadError {
+            filename: path.display().to_string(),
+            source,
+        }]
+    })?;
+
+    Ok((migrations, files))
+}
 
-    migrations.sort_by_key(|m| m.version);
+/// Every file in `path`, to be included so that cargo rebuilds when one of
+/// them changes.
+fn tracked_files(path: &Path) -> io::Result<Vec<String>> {
+    let mut files = vec![];
 
-    quote! {
-        sqlx_migrate::Migrator::new(
-            vec![ #(#migrations),* ]
-        )
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+
+        if entry.file_type()?.is_file() {
+            files.push(entry.path().to_string_lossy().into_owned());
+        }
     }
+
+    files.sort();
+
+    Ok(files)
 }
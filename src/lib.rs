@@ -1,4 +1,9 @@
-pub use sqlx_migrate_common::{Migration, MigrationError, Migrator};
+pub use sqlx_migrate_common::{
+    collect_migrations, dialect, read_migrations, split_statements, split_statements_for, Backend,
+    BoxFuture, Dialect, Migration, MigrationError, MigrationState, MigrationStatus, MigrationStep,
+    Migrator, MissingPolicy, ModifiedMigration, NamingScheme, OutOfOrder, Plan, PlannedMigration,
+    ReadOptions, Statement, Step, Syntax,
+};
 pub use sqlx_migrate_macros::embed;
 
 #[macro_export]
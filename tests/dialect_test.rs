@@ -0,0 +1,67 @@
+use sqlx_migrate::{
+    dialect::{MySqlDialect, PostgresDialect},
+    Dialect,
+};
+
+fn collapse(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[test]
+fn test_postgres_upgrade() {
+    let upgrades =
+        PostgresDialect.upgrade_table(r#""app"."migrations""#, Some("app"), "migrations");
+
+    assert_eq!(3, upgrades.len());
+    assert!(collapse(&upgrades[0].check).ends_with(
+        "WHERE table_schema = 'app' AND table_name = 'migrations' \
+         AND column_name = 'execution_time'"
+    ));
+    assert_eq!(
+        vec!["CAST(0 AS BIGINT) AS execution_time"],
+        upgrades[0].defaults
+    );
+
+    let namespace = &upgrades[2];
+    assert_eq!(3, namespace.statements.len());
+    assert!(collapse(&namespace.statements[1]).contains(
+        r#"WHERE conrelid = '"app"."migrations"'::regclass AND contype = 'p'; EXECUTE format('ALTER TABLE %s DROP CONSTRAINT %I', '"app"."migrations"', pkey);"#
+    ));
+    assert_eq!(
+        1,
+        PostgresDialect
+            .split_statements(&namespace.statements[1])
+            .len()
+    );
+    assert_eq!(
+        r#"ALTER TABLE "app"."migrations" ADD PRIMARY KEY (namespace, version)"#,
+        namespace.statements[2]
+    );
+
+    let upgrades = PostgresDialect.upgrade_table(r#""migrations""#, None, "migrations");
+    assert!(upgrades[2]
+        .check
+        .contains("table_schema = current_schema()"));
+}
+
+#[test]
+fn test_mysql_upgrade() {
+    let upgrades = MySqlDialect.upgrade_table("`migrations`", None, "migrations");
+
+    assert_eq!(3, upgrades.len());
+    assert!(upgrades[1].check.contains("table_schema = DATABASE()"));
+    assert_eq!(
+        vec!["CAST(0 AS SIGNED) AS execution_time"],
+        upgrades[0].defaults
+    );
+
+    let namespace = &upgrades[2];
+    assert_eq!(1, namespace.statements.len());
+    assert_eq!(
+        "ALTER TABLE `migrations` \
+         ADD COLUMN namespace VARCHAR(255) NOT NULL DEFAULT '' FIRST, \
+         DROP PRIMARY KEY, \
+         ADD PRIMARY KEY (namespace, version)",
+        collapse(&namespace.statements[0])
+    );
+}
@@ -1,4 +1,7 @@
-use sqlx_migrate::Migrator;
+use sqlx_migrate::{
+    collect_migrations, read_migrations, Migration, MigrationError, Migrator, NamingScheme,
+    ReadOptions,
+};
 
 #[test]
 fn test_simple_load() {
@@ -14,3 +17,180 @@ fn test_simple_load() {
         m.migrations[0].checksum
     );
 }
+
+#[test]
+fn test_reversible_load() {
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+
+    assert_eq!(2, m.migrations.len());
+    assert_eq!("create_users", m.migrations[0].name);
+    assert_eq!(
+        "CREATE TABLE users (id BIGINT PRIMARY KEY);",
+        m.migrations[0].sql
+    );
+    assert_eq!(
+        Some("DROP TABLE users;"),
+        m.migrations[0].down_sql.as_deref()
+    );
+    assert_eq!("index_users", m.migrations[1].name);
+    assert_eq!(None, m.migrations[1].down_sql);
+}
+
+#[test]
+fn test_no_transaction_load() {
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/no_transaction");
+
+    assert_eq!(3, m.migrations.len());
+    assert!(!m.migrations[0].no_transaction);
+    assert!(m.migrations[1].no_transaction);
+    assert_eq!("vacuum", m.migrations[1].name);
+    assert!(m.migrations[2].no_transaction);
+}
+
+#[test]
+fn test_invalid_filename() {
+    match read_migrations("tests/stubs/invalid_name") {
+        Err(MigrationError::FilenameError { filename, reason }) => {
+            assert_eq!("1614877844_CreateUsers.sql", filename);
+            assert_eq!(
+                "the name may only contain lowercase letters and underscores",
+                reason
+            );
+        }
+        other => panic!("unexpected result: {:?}", other.map(|m| m.len())),
+    }
+}
+
+#[test]
+fn test_collect_all_errors() {
+    let errors: Vec<String> = collect_migrations("tests/stubs/broken", &ReadOptions::default())
+        .unwrap_err()
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(3, errors.len());
+    assert!(errors[0].starts_with("Invalid migration filename 1614877850_Invalid.sql"));
+    assert_eq!(
+        "Cannot read 1614877950_binary.sql: not valid UTF-8",
+        errors[1]
+    );
+    assert!(errors[2].starts_with("Invalid migration filename 1614877900_orphan.down.sql"));
+}
+
+#[test]
+fn test_duplicate_versions() {
+    assert_eq!(
+        "Migrations 1614877844_a.sql and 1614877844_b.sql have the same version 1614877844",
+        read_migrations("tests/stubs/duplicate")
+            .unwrap_err()
+            .to_string()
+    );
+
+    let migration = |name: &str| Migration {
+        batch: false,
+        checksum: String::new(),
+        down_sql: None,
+        name: name.to_owned(),
+        no_transaction: false,
+        repeatable: false,
+        source: None,
+        sql: String::new(),
+        step: None,
+        version: 1,
+    };
+
+    match Migrator::try_new(vec![migration("a"), migration("b")]) {
+        Err(MigrationError::DuplicateError {
+            version,
+            first,
+            second,
+        }) => {
+            assert_eq!(1, version);
+            assert_eq!("1_a", first);
+            assert_eq!("1_b", second);
+        }
+        _ => panic!("duplicate versions were accepted"),
+    }
+}
+
+#[test]
+fn test_skip_non_migrations() {
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/mixed");
+
+    assert_eq!(1, m.migrations.len());
+    assert_eq!("simple_migration", m.migrations[0].name);
+
+    let errors: Vec<String> =
+        collect_migrations("tests/stubs/mixed", &ReadOptions::default().strict(true))
+            .unwrap_err()
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+
+    assert_eq!(
+        vec![
+            "Invalid migration filename .gitkeep: only migrations are allowed in strict mode",
+            "Invalid migration filename README.md: only migrations are allowed in strict mode",
+            "Invalid migration filename notes: subdirectories are not allowed in strict mode",
+        ],
+        errors
+    );
+}
+
+#[test]
+fn test_flyway_load() {
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/flyway", naming = "flyway");
+
+    assert_eq!(2, m.migrations.len());
+    assert_eq!("create_users", m.migrations[0].name);
+    assert_eq!(
+        Some("DROP TABLE users;"),
+        m.migrations[0].down_sql.as_deref()
+    );
+    assert_eq!("Add-Index2", m.migrations[1].name);
+    assert_eq!(2, m.migrations[1].version);
+}
+
+#[test]
+fn test_repeatable_load() {
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/repeatable");
+
+    assert_eq!(2, m.migrations.len());
+    assert!(!m.migrations[0].repeatable);
+    assert!(m.migrations[1].repeatable);
+    assert_eq!("admins_view", m.migrations[1].name);
+    assert!(m.migrations[1].version < 0);
+    assert_eq!(
+        "tests/stubs/repeatable/R__admins_view",
+        m.migrations[1].to_string()
+    );
+
+    assert!(read_migrations("tests/stubs/invalid_repeatable")
+        .unwrap_err()
+        .to_string()
+        .contains("the name of a repeatable migration"));
+}
+
+#[test]
+fn test_naming_schemes() {
+    let read = |naming| {
+        collect_migrations(
+            "tests/stubs/relaxed",
+            &ReadOptions::default().naming(naming),
+        )
+    };
+
+    let relaxed = read(NamingScheme::Relaxed).unwrap();
+    let sqlx_cli = read(NamingScheme::SqlxCli).unwrap();
+    let default = read(NamingScheme::Default);
+    let flyway = read(NamingScheme::Flyway);
+
+    assert_eq!("add_user2fa", relaxed[0].name);
+    assert_eq!("add-index", relaxed[1].name);
+    assert_eq!(2, sqlx_cli.len());
+    assert_eq!(2, default.unwrap_err().len());
+    assert_eq!(
+        "Invalid migration filename 20210301_add_user2fa.sql: expected a `V` or `U` prefix",
+        flyway.unwrap_err()[0].to_string()
+    );
+}
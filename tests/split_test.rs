@@ -0,0 +1,140 @@
+use sqlx_migrate::{split_statements, split_statements_for, Statement, Syntax};
+
+fn sql(input: &str) -> Vec<&str> {
+    split_statements(input).into_iter().map(|s| s.sql).collect()
+}
+
+#[test]
+fn test_split_simple() {
+    assert_eq!(
+        vec![
+            Statement {
+                sql: "SELECT 1",
+                line: 1
+            },
+            Statement {
+                sql: "SELECT 2",
+                line: 3
+            },
+        ],
+        split_statements("SELECT 1;\n\nSELECT 2;\n-- trailing comment\n")
+    );
+}
+
+#[test]
+fn test_split_quotes_and_comments() {
+    assert_eq!(
+        vec![
+            "INSERT INTO t VALUES ('a;b', 'it''s;', E'\\';')",
+            "SELECT \"weird;name\" FROM t",
+            "-- one; two\nSELECT 1",
+            "/* outer /* inner; */ still; */ SELECT 2",
+        ],
+        sql("INSERT INTO t VALUES ('a;b', 'it''s;', E'\\';');\n\
+             SELECT \"weird;name\" FROM t;\n\
+             -- one; two\nSELECT 1;\n\
+             /* outer /* inner; */ still; */ SELECT 2;")
+    );
+}
+
+#[test]
+fn test_split_dollar_quotes() {
+    let input = "CREATE FUNCTION f() RETURNS int AS $body$\n\
+                 BEGIN\n  PERFORM $$;$$;\n  RETURN 1;\nEND;\n$body$ LANGUAGE plpgsql;\n\
+                 DO $$ BEGIN RAISE NOTICE ';'; END $$;\n\
+                 SELECT $1;";
+
+    let statements = split_statements(input);
+
+    assert_eq!(3, statements.len());
+    assert!(statements[0].sql.ends_with("$body$ LANGUAGE plpgsql"));
+    assert_eq!(7, statements[1].line);
+    assert_eq!("SELECT $1", statements[2].sql);
+}
+
+#[test]
+fn test_split_mysql() {
+    let statements: Vec<&str> = split_statements_for(
+        "SELECT 'a\\';b', \"c\\\";d\";\n# c; x\nSELECT `e;f`;",
+        Syntax::MySql,
+    )
+    .into_iter()
+    .map(|s| s.sql)
+    .collect();
+
+    assert_eq!(
+        vec!["SELECT 'a\\';b', \"c\\\";d\"", "# c; x\nSELECT `e;f`"],
+        statements
+    );
+}
+
+#[test]
+fn test_split_sqlite_triggers() {
+    let input = "CREATE TEMP TRIGGER touch AFTER INSERT ON users\n\
+                 BEGIN\n\
+                 \x20   UPDATE users SET kind = CASE WHEN NEW.id = 1 THEN 'a;' ELSE 'b' END;\n\
+                 \x20   DELETE FROM [odd;name];\n\
+                 END;\n\
+                 BEGIN;\n\
+                 SELECT 1;";
+
+    let statements = split_statements_for(input, Syntax::Sqlite);
+
+    assert_eq!(3, statements.len());
+    assert!(statements[0].sql.ends_with("END"));
+    assert_eq!("BEGIN", statements[1].sql);
+    assert_eq!(6, statements[1].line);
+
+    assert!(split_statements(input).len() > 3);
+}
+
+#[test]
+fn test_split_mysql_routines() {
+    let input = "CREATE DEFINER = root@localhost PROCEDURE fill(n INT)\n\
+                 BEGIN\n\
+                 \x20   DECLARE i INT DEFAULT 0;\n\
+                 \x20   WHILE i < n DO\n\
+                 \x20       IF i % 2 = 0 THEN\n\
+                 \x20           INSERT INTO t VALUES (CASE WHEN i = 0 THEN 'a;' ELSE 'b' END);\n\
+                 \x20       END IF;\n\
+                 \x20       SET i = i + 1;\n\
+                 \x20   END WHILE;\n\
+                 END;\n\
+                 CREATE TRIGGER touch BEFORE UPDATE ON t FOR EACH ROW SET NEW.n = 1;\n\
+                 CREATE EVENT purge ON SCHEDULE EVERY 1 DAY DO BEGIN DELETE FROM t; END;\n\
+                 CREATE TABLE event (id INT);\n\
+                 SELECT 1;";
+
+    let statements = split_statements_for(input, Syntax::MySql);
+
+    assert_eq!(5, statements.len());
+    assert!(statements[0].sql.ends_with("END WHILE;\nEND"));
+    assert_eq!(11, statements[1].line);
+    assert!(statements[2].sql.ends_with("DELETE FROM t; END"));
+    assert_eq!("CREATE TABLE event (id INT)", statements[3].sql);
+    assert_eq!("SELECT 1", statements[4].sql);
+}
+
+#[test]
+fn test_split_begin_atomic() {
+    let input = "CREATE OR REPLACE FUNCTION f(a int) RETURNS int LANGUAGE sql\n\
+                 BEGIN ATOMIC\n\
+                 \x20   INSERT INTO t VALUES (CASE WHEN a > 0 THEN a END);\n\
+                 \x20   SELECT a;\n\
+                 END;\n\
+                 BEGIN;\n\
+                 SELECT 1;";
+
+    assert_eq!(
+        vec![
+            "CREATE OR REPLACE FUNCTION f(a int) RETURNS int LANGUAGE sql\n\
+             BEGIN ATOMIC\n\
+             \x20   INSERT INTO t VALUES (CASE WHEN a > 0 THEN a END);\n\
+             \x20   SELECT a;\n\
+             END",
+            "BEGIN",
+            "SELECT 1",
+        ],
+        sql(input)
+    );
+}
@@ -0,0 +1,786 @@
+use sqlx::sqlite::{SqliteConnection, SqlitePool, SqlitePoolOptions};
+use sqlx_migrate::{
+    dialect::SqliteDialect, BoxFuture, Dialect, Migration, MigrationError, MigrationState,
+    MigrationStep, Migrator, MissingPolicy, NamingScheme, OutOfOrder, ReadOptions,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+
+async fn connect() -> SqlitePool {
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+}
+
+async fn applied_versions(db: &SqlitePool) -> Vec<i64> {
+    sqlx::query_scalar("SELECT version FROM migrations ORDER BY version")
+        .fetch_all(db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_migrate() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+
+    m.migrate(&db).await.unwrap();
+    m.migrate(&db).await.unwrap();
+
+    assert_eq!(vec![1614877844, 1614877900], applied_versions(&db).await);
+    sqlx::query("SELECT id FROM users")
+        .fetch_all(&db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_from_dir() {
+    let db = connect().await;
+    let embedded: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+    let m = Migrator::from_dir_async("tests/stubs/reversible")
+        .await
+        .unwrap();
+
+    assert_eq!(2, m.migrations.len());
+    for (a, b) in m.migrations.iter().zip(&embedded.migrations) {
+        assert_eq!(a.checksum, b.checksum);
+        assert_eq!(a.down_sql, b.down_sql);
+    }
+
+    m.migrate(&db).await.unwrap();
+    embedded.migrate(&db).await.unwrap();
+    assert_eq!(vec![1614877844, 1614877900], applied_versions(&db).await);
+
+    assert!(Migrator::from_dir("tests/stubs/missing").is_err());
+}
+
+#[tokio::test]
+async fn test_from_dir_with() {
+    let db = connect().await;
+    let embedded: Migrator = sqlx_migrate::embed!("tests/stubs/flyway", naming = "flyway");
+    let options = ReadOptions::default().naming(NamingScheme::Flyway);
+
+    assert!(Migrator::from_dir("tests/stubs/flyway").is_err());
+
+    let m = Migrator::from_dir_with_async("tests/stubs/flyway", &options)
+        .await
+        .unwrap();
+
+    assert_eq!(embedded.migrations.len(), m.migrations.len());
+    for (a, b) in m.migrations.iter().zip(&embedded.migrations) {
+        assert_eq!(a.version, b.version);
+        assert_eq!(a.checksum, b.checksum);
+        assert_eq!(a.down_sql, b.down_sql);
+    }
+
+    m.migrate(&db).await.unwrap();
+    assert_eq!(vec![1, 2], applied_versions(&db).await);
+
+    let strict = options.strict(true);
+    assert!(Migrator::from_dir_with("tests/stubs/mixed", &strict).is_err());
+}
+
+#[tokio::test]
+async fn test_multiple_sources() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/sets/billing", "tests/stubs/sets/auth");
+
+    assert_eq!("create_users", m.migrations[0].name);
+    assert_eq!("create_invoices", m.migrations[1].name);
+
+    m.migrate(&db).await.unwrap();
+
+    let sources: Vec<String> = sqlx::query_scalar("SELECT source FROM migrations ORDER BY version")
+        .fetch_all(&db)
+        .await
+        .unwrap();
+    assert_eq!(
+        vec!["tests/stubs/sets/auth", "tests/stubs/sets/billing"],
+        sources
+    );
+}
+
+struct RenameAdmin;
+
+impl MigrationStep<SqliteConnection> for RenameAdmin {
+    fn run<'c>(
+        &'c self,
+        conn: &'c mut SqliteConnection,
+    ) -> BoxFuture<'c, Result<(), MigrationError>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE users SET id = 2 WHERE id = 1")
+                .execute(conn)
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_rust_step() {
+    let db = connect().await;
+    let m = sqlx_migrate::embed!("tests/stubs/reversible")
+        .step(1614877999, "rename_admin", "v1", RenameAdmin)
+        .step_fn(
+            1614877850,
+            "insert_admin",
+            "v1",
+            |conn: &mut SqliteConnection| {
+                Box::pin(async move {
+                    sqlx::query("INSERT INTO users (id) VALUES (1)")
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                })
+            },
+        );
+
+    assert_eq!("insert_admin", m.migrations[1].name);
+
+    m.migrate(&db).await.unwrap();
+    assert_eq!(
+        vec![1614877844, 1614877850, 1614877900, 1614877999],
+        applied_versions(&db).await
+    );
+
+    let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM users")
+        .fetch_all(&db)
+        .await
+        .unwrap();
+    assert_eq!(vec![2], ids);
+
+    let status = m.status(&db).await.unwrap();
+    assert_eq!("v1", status[1].checksum);
+    assert_eq!(MigrationState::Applied, status[1].state);
+}
+
+#[tokio::test]
+async fn test_vars() {
+    let mut vars = HashMap::new();
+
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/templated");
+    match m.migrate(&db).await {
+        Err(MigrationError::VariableError { version, name }) => {
+            assert_eq!(1614877844, version);
+            assert_eq!("table", name);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    for table in &["accounts", "users"] {
+        let db = connect().await;
+        vars.insert(String::from("table"), table.to_string());
+        let m: Migrator = sqlx_migrate::embed!("tests/stubs/templated").vars(vars.clone());
+        m.migrate(&db).await.unwrap();
+
+        sqlx::query(&format!("SELECT id FROM {}", table))
+            .fetch_all(&db)
+            .await
+            .unwrap();
+
+        let checksum: String = sqlx::query_scalar("SELECT checksum FROM migrations")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(m.migrations[0].checksum, checksum);
+
+        m.rollback(&db, 1).await.unwrap();
+        assert!(applied_versions(&db).await.is_empty());
+        assert!(sqlx::query(&format!("SELECT id FROM {}", table))
+            .fetch_all(&db)
+            .await
+            .is_err());
+    }
+
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/templated");
+    m.migrate_with_vars(&db, &vars).await.unwrap();
+    assert_eq!(vec![1614877844], applied_versions(&db).await);
+}
+
+#[tokio::test]
+async fn test_rollback() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+
+    m.migrate_to(&db, 1614877844).await.unwrap();
+    assert_eq!(vec![1614877844], applied_versions(&db).await);
+
+    m.rollback(&db, 1).await.unwrap();
+    assert!(applied_versions(&db).await.is_empty());
+    assert!(sqlx::query("SELECT id FROM users")
+        .fetch_all(&db)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_rollback_irreversible() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+
+    m.migrate(&db).await.unwrap();
+
+    assert!(m.rollback(&db, 2).await.is_err());
+    assert_eq!(vec![1614877844, 1614877900], applied_versions(&db).await);
+}
+
+#[tokio::test]
+async fn test_checksum_mismatch() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+
+    m.migrate(&db).await.unwrap();
+    sqlx::query("UPDATE migrations SET checksum = 'changed' WHERE version = 1614877900")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    match m.migrate(&db).await {
+        Err(MigrationError::ChecksumError {
+            version,
+            name,
+            expected,
+            actual,
+        }) => {
+            assert_eq!(1614877900, version);
+            assert_eq!("index_users", name);
+            assert_eq!("changed", expected);
+            assert_eq!(m.migrations[1].checksum, actual);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_statement_error() {
+    let db = connect().await;
+    let m = Migrator::new(vec![Migration {
+        batch: false,
+        checksum: String::from("checksum"),
+        down_sql: None,
+        name: String::from("broken"),
+        no_transaction: false,
+        repeatable: false,
+        source: None,
+        sql: String::from("CREATE TABLE users (id BIGINT);\n\nINSERT INTO nope VALUES (1);"),
+        step: None,
+        version: 1,
+    }]);
+
+    let err = m.migrate(&db).await.unwrap_err();
+
+    match &err {
+        MigrationError::StatementError {
+            version,
+            index,
+            line,
+            sql,
+            ..
+        } => {
+            assert_eq!(1, *version);
+            assert_eq!(2, *index);
+            assert_eq!(3, *line);
+            assert_eq!("INSERT INTO nope VALUES (1)", sql);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    assert!(err
+        .to_string()
+        .starts_with("Statement 2 of migration 1 on line 3 failed"));
+    assert!(applied_versions(&db).await.is_empty());
+}
+
+struct CustomDialect;
+
+impl Dialect for CustomDialect {
+    fn create_table(&self, table: &str) -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (version BIGINT, name TEXT, checksum TEXT, ms BIGINT)",
+            table
+        )
+    }
+
+    fn select_migrations(&self, table: &str) -> String {
+        format!(
+            "SELECT '' AS namespace, version, name, checksum, 1 AS success, \
+             ms AS execution_time, 'unknown' AS applied_at FROM {} ORDER BY version",
+            table
+        )
+    }
+
+    fn insert_migration(&self, table: &str) -> String {
+        format!("INSERT INTO {} VALUES (?1, ?2, ?3, ?4)", table)
+    }
+
+    fn delete_migration(&self, table: &str) -> String {
+        format!("DELETE FROM {} WHERE version = ?1", table)
+    }
+}
+
+#[tokio::test]
+async fn test_custom_dialect() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible")
+        .dialect(CustomDialect)
+        .table("history");
+
+    m.migrate(&db).await.unwrap();
+
+    let versions: Vec<i64> = sqlx::query_scalar("SELECT version FROM history ORDER BY version")
+        .fetch_all(&db)
+        .await
+        .unwrap();
+
+    assert_eq!(vec![1614877844, 1614877900], versions);
+}
+
+#[tokio::test]
+async fn test_batch() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/batch");
+
+    assert!(!m.migrations[0].batch);
+    assert!(m.migrations[1].batch);
+
+    m.migrate(&db).await.unwrap();
+
+    sqlx::query("INSERT INTO users (id) VALUES (1)")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let updated_at: String = sqlx::query_scalar("SELECT updated_at FROM users")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+
+    assert_eq!("now", updated_at);
+}
+
+#[tokio::test]
+async fn test_no_transaction() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/no_transaction");
+
+    m.migrate(&db).await.unwrap();
+
+    assert_eq!(
+        vec![1614877844, 1614877900, 1614877950],
+        applied_versions(&db).await
+    );
+}
+
+/// Behaves like the SQLite dialect, but the migration lock is always taken.
+struct LockedDialect;
+
+impl Dialect for LockedDialect {
+    fn create_table(&self, table: &str) -> String {
+        SqliteDialect.create_table(table)
+    }
+
+    fn select_migrations(&self, table: &str) -> String {
+        SqliteDialect.select_migrations(table)
+    }
+
+    fn insert_migration(&self, table: &str) -> String {
+        SqliteDialect.insert_migration(table)
+    }
+
+    fn delete_migration(&self, table: &str) -> String {
+        SqliteDialect.delete_migration(table)
+    }
+
+    fn try_lock(&self, _key: i64) -> Option<String> {
+        Some(String::from("SELECT 1 WHERE 0"))
+    }
+}
+
+#[tokio::test]
+async fn test_lock() {
+    let db = connect().await;
+
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible").dialect(LockedDialect);
+    assert!(!m.try_migrate(&db).await.unwrap());
+
+    let m = m.lock_timeout(Duration::from_millis(200));
+    assert!(matches!(
+        m.migrate(&db).await,
+        Err(MigrationError::LockError)
+    ));
+
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+    assert!(m.try_migrate(&db).await.unwrap());
+    assert_eq!(vec![1614877844, 1614877900], applied_versions(&db).await);
+}
+
+#[tokio::test]
+async fn test_table_and_schema() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible")
+        .table("schema \"history\"")
+        .schema("main");
+
+    m.migrate(&db).await.unwrap();
+
+    let versions: Vec<i64> =
+        sqlx::query_scalar(r#"SELECT version FROM main."schema ""history""" ORDER BY version"#)
+            .fetch_all(&db)
+            .await
+            .unwrap();
+
+    assert_eq!(vec![1614877844, 1614877900], versions);
+    assert!(sqlx::query("SELECT version FROM migrations")
+        .fetch_all(&db)
+        .await
+        .is_err());
+}
+
+/// Behaves like the SQLite dialect, but fails to create schemas.
+struct SchemaDialect;
+
+impl Dialect for SchemaDialect {
+    fn select_schema(&self, schema: &str) -> Option<String> {
+        Some(format!(
+            "SELECT 1 FROM pragma_database_list WHERE name = {}",
+            self.quote_literal(schema)
+        ))
+    }
+
+    fn create_schema(&self, schema: &str) -> Option<String> {
+        Some(format!("CREATE SCHEMA {}", schema))
+    }
+
+    fn create_table(&self, table: &str) -> String {
+        SqliteDialect.create_table(table)
+    }
+
+    fn select_migrations(&self, table: &str) -> String {
+        SqliteDialect.select_migrations(table)
+    }
+
+    fn insert_migration(&self, table: &str) -> String {
+        SqliteDialect.insert_migration(table)
+    }
+
+    fn delete_migration(&self, table: &str) -> String {
+        SqliteDialect.delete_migration(table)
+    }
+}
+
+#[tokio::test]
+async fn test_existing_schema() {
+    let db = connect().await;
+
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible")
+        .dialect(SchemaDialect)
+        .schema("main");
+    m.migrate(&db).await.unwrap();
+    assert_eq!(vec![1614877844, 1614877900], applied_versions(&db).await);
+
+    let m = m.schema("missing");
+    assert!(m.migrate(&db).await.is_err());
+}
+
+#[tokio::test]
+async fn test_plan() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+
+    let plan = m.plan(&db).await.unwrap();
+    assert_eq!(2, plan.pending.len());
+    assert!(!plan.is_up_to_date());
+    assert!(sqlx::query("SELECT version FROM migrations")
+        .fetch_all(&db)
+        .await
+        .is_err());
+
+    m.migrate_to(&db, 1614877844).await.unwrap();
+
+    let plan = m.plan(&db).await.unwrap();
+    assert_eq!(
+        "applied  1614877844 create_users\npending  1614877900 index_users\n",
+        plan.to_string()
+    );
+    assert_eq!(
+        r#"{"applied":[{"name":"create_users","repeatable":false,"version":1614877844}],"pending":[{"name":"index_users","repeatable":false,"version":1614877900}],"modified":[],"missing":[],"out_of_order":[]}"#,
+        serde_json::to_string(&plan).unwrap()
+    );
+
+    sqlx::query("UPDATE migrations SET checksum = 'changed'")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let plan = m.plan(&db).await.unwrap();
+    assert_eq!(1, plan.modified.len());
+    assert_eq!("changed", plan.modified[0].applied_checksum);
+}
+
+#[tokio::test]
+async fn test_repeatable() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/repeatable");
+    let checksum = m.migrations[1].checksum.clone();
+
+    m.migrate(&db).await.unwrap();
+    m.migrate(&db).await.unwrap();
+
+    let plan = m.plan(&db).await.unwrap();
+    assert!(plan.is_up_to_date());
+    assert_eq!(
+        "applied  1614877844 create_users\napplied  R__admins_view\n",
+        plan.to_string()
+    );
+
+    sqlx::query("INSERT INTO users (name, admin) VALUES ('root', TRUE)")
+        .execute(&db)
+        .await
+        .unwrap();
+    sqlx::query("DROP VIEW admins").execute(&db).await.unwrap();
+    sqlx::query("UPDATE migrations SET checksum = 'changed' WHERE version < 0")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let status = m.status(&db).await.unwrap();
+    assert_eq!(MigrationState::Applied, status[0].state);
+    assert!(status[1].repeatable);
+    assert_eq!(MigrationState::Pending, status[1].state);
+    assert_eq!(1, m.plan(&db).await.unwrap().pending.len());
+
+    m.migrate(&db).await.unwrap();
+
+    let names: Vec<String> = sqlx::query_scalar("SELECT name FROM admins")
+        .fetch_all(&db)
+        .await
+        .unwrap();
+    assert_eq!(vec!["root"], names);
+
+    let recorded: Vec<String> =
+        sqlx::query_scalar("SELECT checksum FROM migrations WHERE version < 0")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+    assert_eq!(vec![checksum], recorded);
+
+    m.rollback(&db, 1).await.unwrap_err();
+    assert_eq!(2, applied_versions(&db).await.len());
+
+    let mut m: Migrator = sqlx_migrate::embed!("tests/stubs/repeatable");
+    m.migrations.pop();
+
+    match m.migrate(&db).await {
+        Err(MigrationError::MissingError(missing)) => assert_eq!(vec!["R__admins_view"], missing),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_status() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+
+    let status = m.status(&db).await.unwrap();
+    assert_eq!(2, status.len());
+    assert!(status.iter().all(|s| s.state == MigrationState::Pending));
+
+    m.migrate_to(&db, 1614877844).await.unwrap();
+    sqlx::query(
+        "INSERT INTO migrations (version, name, checksum, execution_time) \
+         VALUES (1614877999, 'removed', 'abc', 42)",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let status = m.status(&db).await.unwrap();
+    let states: Vec<(i64, MigrationState)> = status.iter().map(|s| (s.version, s.state)).collect();
+
+    assert_eq!(
+        vec![
+            (1614877844, MigrationState::Applied),
+            (1614877900, MigrationState::Pending),
+            (1614877999, MigrationState::MissingLocally),
+        ],
+        states
+    );
+    assert!(status[0].applied_at.is_some());
+    assert_eq!(None, status[1].applied_at);
+    assert_eq!("removed", status[2].name);
+    assert_eq!(Some(Duration::from_millis(42)), status[2].execution_time);
+}
+
+#[tokio::test]
+async fn test_missing_locally() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+
+    m.migrate_to(&db, 1614877844).await.unwrap();
+    sqlx::query(
+        "INSERT INTO migrations (version, name, checksum, execution_time) \
+         VALUES (1614877850, 'removed', 'abc', 0)",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let plan = m.plan(&db).await.unwrap();
+    assert_eq!("removed", plan.missing[0].name);
+    assert!(plan.would_fail());
+
+    match m.migrate(&db).await {
+        Err(MigrationError::MissingError(missing)) => {
+            assert_eq!(vec!["1614877850_removed"], missing)
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(vec![1614877844, 1614877850], applied_versions(&db).await);
+
+    let m = m.missing_policy(MissingPolicy::Ignore);
+    assert!(!m.plan(&db).await.unwrap().would_fail());
+    m.migrate(&db).await.unwrap();
+    assert!(m.plan(&db).await.unwrap().is_up_to_date());
+    assert_eq!(
+        vec![1614877844, 1614877850, 1614877900],
+        applied_versions(&db).await
+    );
+}
+
+#[tokio::test]
+async fn test_namespaces() {
+    let db = connect().await;
+    let app = sqlx_migrate::embed!("tests/stubs/reversible").namespace("app");
+    let lib = sqlx_migrate::embed!("tests/stubs/simple").namespace("lib");
+
+    app.migrate(&db).await.unwrap();
+    lib.migrate(&db).await.unwrap();
+    app.migrate(&db).await.unwrap();
+
+    assert_eq!(2, app.status(&db).await.unwrap().len());
+    assert_eq!(1, lib.status(&db).await.unwrap().len());
+
+    let status: Vec<(String, i64, MigrationState)> = lib
+        .status_all(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| (s.namespace, s.version, s.state))
+        .collect();
+
+    assert_eq!(
+        vec![
+            (String::from("app"), 1614877844, MigrationState::Applied),
+            (String::from("app"), 1614877900, MigrationState::Applied),
+            (String::from("lib"), 1614877844, MigrationState::Applied),
+        ],
+        status
+    );
+
+    assert_eq!(
+        vec![1614877844, 1614877844, 1614877900],
+        applied_versions(&db).await
+    );
+}
+
+#[tokio::test]
+async fn test_out_of_order() {
+    let db = connect().await;
+
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+    m.migrate(&db).await.unwrap();
+
+    let m: Migrator =
+        sqlx_migrate::embed!("tests/stubs/out_of_order").out_of_order(OutOfOrder::Reject);
+
+    let plan = m.plan(&db).await.unwrap();
+    assert_eq!(vec![1614877870], plan.out_of_order);
+    assert!(plan.would_fail());
+
+    match m.migrate(&db).await {
+        Err(MigrationError::OutOfOrderError(versions)) => assert_eq!(vec![1614877870], versions),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let m = m.out_of_order(OutOfOrder::Warn);
+    assert!(!m.plan(&db).await.unwrap().would_fail());
+    m.migrate(&db).await.unwrap();
+    assert_eq!(
+        vec![1614877844, 1614877870, 1614877900],
+        applied_versions(&db).await
+    );
+}
+
+#[tokio::test]
+async fn test_upgrade_table() {
+    let db = connect().await;
+    let m: Migrator = sqlx_migrate::embed!("tests/stubs/reversible");
+
+    sqlx::query(
+        "CREATE TABLE migrations (
+            version     BIGINT PRIMARY KEY,
+            name        TEXT NOT NULL,
+            checksum    VARCHAR(64),
+            created_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO migrations (version, name, checksum) \
+         VALUES (1614877844, 'create_users', ?)",
+    )
+    .bind(&m.migrations[0].checksum)
+    .execute(&db)
+    .await
+    .unwrap();
+    sqlx::query(&m.migrations[0].sql)
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let plan = m.plan(&db).await.unwrap();
+    assert_eq!(1, plan.applied.len());
+    assert_eq!(1, plan.pending.len());
+
+    let status = m.status(&db).await.unwrap();
+    assert_eq!(MigrationState::Applied, status[0].state);
+    assert_eq!(Some(Duration::from_millis(0)), status[0].execution_time);
+    assert_eq!(MigrationState::Pending, status[1].state);
+    assert!(sqlx::query("SELECT namespace FROM migrations")
+        .fetch_all(&db)
+        .await
+        .is_err());
+
+    m.migrate(&db).await.unwrap();
+    m.migrate(&db).await.unwrap();
+    assert_eq!(vec![1614877844, 1614877900], applied_versions(&db).await);
+
+    let status = m.status(&db).await.unwrap();
+    assert_eq!(MigrationState::Applied, status[0].state);
+    assert_eq!(Some(Duration::from_millis(0)), status[0].execution_time);
+
+    let sources: Vec<Option<String>> =
+        sqlx::query_scalar("SELECT source FROM migrations ORDER BY version")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+    assert_eq!(
+        vec![None, Some(String::from("tests/stubs/reversible"))],
+        sources
+    );
+
+    sqlx::query(
+        "INSERT INTO migrations (namespace, version, name, checksum, execution_time) \
+         VALUES ('other', 1614877844, 'create_users', '', 0)",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+}
@@ -0,0 +1,5 @@
+-- sqlx-migrate: batch
+CREATE TRIGGER touch_users AFTER INSERT ON users
+BEGIN
+    UPDATE users SET updated_at = 'now' WHERE id = NEW.id;
+END;
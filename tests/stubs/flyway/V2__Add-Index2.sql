@@ -0,0 +1 @@
+CREATE INDEX users_id ON users (id);
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT 1 AS one;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- sqlx-migrate: no-transaction
+CREATE INDEX users_id ON users (id);
+VACUUM;
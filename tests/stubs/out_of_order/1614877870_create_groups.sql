@@ -0,0 +1 @@
+CREATE TABLE groups (id BIGINT PRIMARY KEY);
@@ -0,0 +1,5 @@
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    admin BOOLEAN NOT NULL DEFAULT FALSE
+);
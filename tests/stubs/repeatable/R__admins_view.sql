@@ -0,0 +1,2 @@
+DROP VIEW IF EXISTS admins;
+CREATE VIEW admins AS SELECT id, name FROM users WHERE admin;
@@ -0,0 +1 @@
+CREATE TABLE users (id BIGINT PRIMARY KEY);
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE TABLE invoices (id BIGINT PRIMARY KEY, user_id BIGINT REFERENCES users (id));
\ No newline at end of file
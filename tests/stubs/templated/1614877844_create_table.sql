@@ -0,0 +1 @@
+CREATE TABLE ${table} (id BIGINT PRIMARY KEY);
\ No newline at end of file